@@ -0,0 +1,140 @@
+use clap::ArgMatches;
+use std::process::exit;
+use txt_cluster::cluster::{
+    Cosine, Dice, Hamming, Jaccard, Jaro, Levenshtein, NormLevenshtein, Phonetic, SimHash,
+    TokenSet, TokenSort, Tokenizer, Unequal, ENGLISH_STOPWORDS, OSA,
+};
+use txt_cluster::phonetic::Encoding;
+
+pub trait FromMatches: Sized {
+    fn from_matches(matches: &ArgMatches) -> Self;
+}
+
+fn ratio(matches: &ArgMatches) -> f64 {
+    matches.value_of("ratio").unwrap().parse::<f64>().unwrap()
+}
+
+fn threshold(matches: &ArgMatches) -> usize {
+    matches
+        .value_of("threshold")
+        .unwrap()
+        .parse::<usize>()
+        .unwrap()
+}
+
+impl FromMatches for Jaro {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        Jaro::new(ratio(matches)).winkler(matches.is_present("winkler"))
+    }
+}
+
+impl FromMatches for Levenshtein {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        Levenshtein::new(threshold(matches)).damerau(matches.is_present("damerau"))
+    }
+}
+
+impl FromMatches for NormLevenshtein {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        NormLevenshtein::new(ratio(matches)).damerau(matches.is_present("damerau"))
+    }
+}
+
+impl FromMatches for OSA {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        OSA::new(threshold(matches))
+    }
+}
+
+impl FromMatches for Dice {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        Dice::new(ratio(matches))
+    }
+}
+
+impl FromMatches for Hamming {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let unequal = match matches.value_of("unequal") {
+            Some("infinite") | None => Unequal::Infinite,
+            Some("pad") => Unequal::Pad,
+            _ => unreachable!(),
+        };
+
+        Hamming::new(threshold(matches)).unequal(unequal)
+    }
+}
+
+impl FromMatches for TokenSort {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        TokenSort::new(ratio(matches))
+    }
+}
+
+impl FromMatches for TokenSet {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        TokenSet::new(ratio(matches))
+    }
+}
+
+impl FromMatches for Jaccard {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let q = matches
+            .value_of("qgram")
+            .unwrap_or("2")
+            .parse::<usize>()
+            .unwrap();
+
+        Jaccard::new(ratio(matches)).q(q)
+    }
+}
+
+impl FromMatches for SimHash {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        SimHash::new(threshold(matches))
+    }
+}
+
+impl FromMatches for Cosine {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let tokenizer = match matches.value_of("tokenize") {
+            Some("alphanumeric") | None => Tokenizer::Alphanumeric,
+            Some("whitespace") => Tokenizer::Whitespace,
+            _ => unreachable!(),
+        };
+
+        let mut cosine = Cosine::new(ratio(matches)).tokenizer(tokenizer);
+        if matches.is_present("stopwords") {
+            cosine = cosine.stopwords(ENGLISH_STOPWORDS);
+        }
+
+        if let Some(path) = matches.value_of("stopwords-file") {
+            match std::fs::read_to_string(path) {
+                Ok(words) => cosine = cosine.stopwords(words.split_whitespace()),
+                Err(err) => {
+                    eprintln!("Error opening '{}': {}", path, err);
+                    exit(1);
+                }
+            }
+        }
+
+        cosine
+    }
+}
+
+impl FromMatches for Phonetic {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let encoding = match matches.value_of("encoding") {
+            Some("metaphone") | None => Encoding::Metaphone,
+            Some("soundex") => Encoding::Soundex,
+            Some("nysiis") => Encoding::Nysiis,
+            _ => unreachable!(),
+        };
+
+        let phonetic = Phonetic::new(encoding);
+        if matches.is_present("threshold") {
+            phonetic.threshold(threshold(matches))
+        } else {
+            phonetic
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use super::ClusterAlgo;
+use rayon::prelude::*;
+
+/// A [`ClusterAlgo`] whose acceptance can be bounded by a true metric.
+///
+/// Any accepted pair must lie within [`Metric::radius`] of each other under
+/// [`Metric::metric`], and the metric must obey the triangle inequality.
+pub trait Metric: ClusterAlgo {
+    /// Distance between two records under the bounding metric.
+    fn metric(&self, first: &str, second: &str) -> usize;
+
+    /// Largest distance an accepted pair may have, if any pair can be accepted.
+    fn radius(&self) -> Option<usize>;
+}
+
+/// Finds candidate pairs through a BK-tree instead of comparing every pair.
+///
+/// Produces the same pairs as the wrapped algorithm on its own.
+pub struct BkTree<M> {
+    metric: M,
+}
+
+impl<M> BkTree<M>
+where
+    M: Metric,
+{
+    /// Indexes records by the bounding metric of `metric`.
+    pub fn new(metric: M) -> BkTree<M> {
+        BkTree { metric }
+    }
+}
+
+impl<M> ClusterAlgo for BkTree<M>
+where
+    M: Metric,
+{
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.metric.accept(first, second)
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        self.metric.distance(first, second)
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        self.metric.cutoff()
+    }
+
+    fn observe(&mut self, record: &str) {
+        self.metric.observe(record)
+    }
+
+    fn pairs(&self, lines: &[String]) -> Vec<(usize, usize)> {
+        let radius = match self.metric.radius() {
+            Some(radius) => radius,
+            None => return Vec::new(),
+        };
+
+        let mut tree = Tree::default();
+        for index in 0..lines.len() {
+            tree.insert(&self.metric, lines, index);
+        }
+
+        (0..lines.len())
+            .into_par_iter()
+            .flat_map(|first| {
+                tree.within(&self.metric, lines, first, radius)
+                    .into_iter()
+                    .filter(|&second| first < second)
+                    .filter(|&second| self.accept(&lines[first], &lines[second]))
+                    .map(|second| (first, second))
+                    .collect::<Vec<(usize, usize)>>()
+            })
+            .collect()
+    }
+}
+
+struct Node {
+    record: usize,
+    same: Vec<usize>,
+    children: Vec<(usize, usize)>,
+}
+
+#[derive(Default)]
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn node(&mut self, record: usize) -> usize {
+        self.nodes.push(Node {
+            record,
+            same: Vec::new(),
+            children: Vec::new(),
+        });
+
+        self.nodes.len() - 1
+    }
+
+    fn insert<M: Metric>(&mut self, metric: &M, lines: &[String], record: usize) {
+        if self.nodes.is_empty() {
+            self.node(record);
+            return;
+        }
+
+        let mut cur = 0;
+        loop {
+            let dist = metric.metric(&lines[self.nodes[cur].record], &lines[record]);
+            if dist == 0 {
+                self.nodes[cur].same.push(record);
+                return;
+            }
+
+            let child = self.nodes[cur]
+                .children
+                .iter()
+                .find(|&&(d, _)| d == dist)
+                .map(|&(_, child)| child);
+
+            match child {
+                Some(child) => cur = child,
+                None => {
+                    let child = self.node(record);
+                    self.nodes[cur].children.push((dist, child));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn within<M: Metric>(
+        &self,
+        metric: &M,
+        lines: &[String],
+        record: usize,
+        radius: usize,
+    ) -> Vec<usize> {
+        let mut found = Vec::new();
+        if self.nodes.is_empty() {
+            return found;
+        }
+
+        let mut stack = vec![0];
+        while let Some(cur) = stack.pop() {
+            let node = &self.nodes[cur];
+            let dist = metric.metric(&lines[node.record], &lines[record]);
+
+            if dist <= radius {
+                found.push(node.record);
+                found.extend_from_slice(&node.same);
+            }
+
+            let low = dist.saturating_sub(radius);
+            let high = dist + radius;
+            for &(d, child) in &node.children {
+                if low <= d && d <= high {
+                    stack.push(child);
+                }
+            }
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{brute_force, lines, sorted};
+    use crate::cluster::{Levenshtein, OSA};
+
+    #[test]
+    fn matches_brute_force() {
+        let lines = lines(&[
+            "ca", "ac", "abc", "hello", "hallo", "hullo", "hello", "world", "words", "sword", "",
+            "a", "abcd", "abdc", "bacd",
+        ]);
+
+        for threshold in 0..5 {
+            let algo = Levenshtein::new(threshold);
+            let expected = brute_force(&algo, &lines);
+            assert_eq!(sorted(BkTree::new(algo).pairs(&lines)), expected);
+
+            let algo = Levenshtein::new(threshold).damerau(true);
+            let expected = brute_force(&algo, &lines);
+            assert_eq!(sorted(BkTree::new(algo).pairs(&lines)), expected);
+
+            let algo = OSA::new(threshold);
+            let expected = brute_force(&algo, &lines);
+            assert_eq!(sorted(BkTree::new(algo).pairs(&lines)), expected);
+        }
+    }
+}
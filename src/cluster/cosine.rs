@@ -0,0 +1,313 @@
+use super::ClusterAlgo;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Common english words that carry little meaning on their own.
+pub const ENGLISH_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been",
+    "but", "by", "can", "could", "did", "do", "does", "for", "from", "had", "has", "have", "he",
+    "her", "his", "how", "i", "if", "in", "into", "is", "it", "its", "may", "more", "no", "not",
+    "of", "on", "or", "our", "out", "she", "should", "so", "some", "such", "than", "that", "the",
+    "their", "them", "then", "there", "these", "they", "this", "to", "up", "was", "we", "were",
+    "what", "when", "which", "who", "will", "with", "would", "you", "your",
+];
+
+/// How records are split into tokens.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tokenizer {
+    /// Runs of alphanumeric characters.
+    Alphanumeric,
+    /// Runs of non-whitespace characters, keeping punctuation such as paths intact.
+    Whitespace,
+}
+
+/// Clusters records whose tf-idf weighted token vectors have a cosine
+/// similarity above a ratio, so shared rare tokens count for more than
+/// shared common ones.
+///
+/// Token weights come from the records given to [`Cosine::fit`] or
+/// [observed](ClusterAlgo::observe) one at a time; until then every token is
+/// weighted equally. Records without tokens are only similar
+/// to each other. Dot products are summed token by token through postings
+/// lists, so records with no token in common, whose similarity is zero, never
+/// meet.
+pub struct Cosine {
+    ratio: f64,
+    tokenizer: Tokenizer,
+    stopwords: HashSet<String>,
+    documents: usize,
+    frequencies: HashMap<String, usize>,
+}
+
+impl Cosine {
+    /// Accepts pairs with a cosine similarity above `ratio`.
+    pub fn new(ratio: f64) -> Cosine {
+        Cosine {
+            ratio,
+            tokenizer: Tokenizer::Alphanumeric,
+            stopwords: HashSet::new(),
+            documents: 0,
+            frequencies: HashMap::new(),
+        }
+    }
+
+    /// Split records into tokens with `tokenizer`.
+    pub fn tokenizer(mut self, tokenizer: Tokenizer) -> Cosine {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Ignore `stopwords`, compared case insensitively.
+    pub fn stopwords<I, S>(mut self, stopwords: I) -> Cosine
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.stopwords.extend(
+            stopwords
+                .into_iter()
+                .map(|word| word.as_ref().to_lowercase()),
+        );
+        self
+    }
+
+    /// Weights tokens by their inverse document frequency over `records`.
+    pub fn fit(mut self, records: &[String]) -> Cosine {
+        let frequencies = records
+            .par_iter()
+            .map(|record| self.tokens(record).collect::<HashSet<&str>>())
+            .collect::<Vec<HashSet<&str>>>();
+
+        let mut counts = HashMap::new();
+        for token in frequencies.into_iter().flatten() {
+            *counts.entry(token.to_string()).or_insert(0) += 1;
+        }
+
+        self.documents = records.len();
+        self.frequencies = counts;
+        self
+    }
+
+    fn tokens<'a>(&'a self, record: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let tokens: Box<dyn Iterator<Item = &'a str>> = match self.tokenizer {
+            Tokenizer::Alphanumeric => Box::new(
+                record
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|token| !token.is_empty()),
+            ),
+            Tokenizer::Whitespace => Box::new(record.split_whitespace()),
+        };
+
+        tokens.filter(move |token| {
+            self.stopwords.is_empty() || !self.stopwords.contains(&token.to_lowercase())
+        })
+    }
+
+    fn idf(&self, token: &str) -> f64 {
+        let frequency = self.frequencies.get(token).cloned().unwrap_or(0);
+        ((1 + self.documents) as f64 / (1 + frequency) as f64).ln() + 1.0
+    }
+
+    /// Unit length tf-idf weights of the tokens in `record`, sorted by token.
+    fn vector<'a>(&'a self, record: &'a str) -> Vec<(&'a str, f64)> {
+        let mut counts = HashMap::new();
+        for token in self.tokens(record) {
+            *counts.entry(token).or_insert(0usize) += 1;
+        }
+
+        let mut vector = counts
+            .into_iter()
+            .map(|(token, count)| (token, count as f64 * self.idf(token)))
+            .collect::<Vec<(&str, f64)>>();
+
+        let norm = vector
+            .iter()
+            .map(|(_, weight)| weight * weight)
+            .sum::<f64>()
+            .sqrt();
+        for (_, weight) in vector.iter_mut() {
+            *weight /= norm;
+        }
+
+        vector.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        vector
+    }
+
+    fn similarity(&self, first: &str, second: &str) -> f64 {
+        let first = self.vector(first);
+        let second = self.vector(second);
+        if first.is_empty() || second.is_empty() {
+            return if first.is_empty() && second.is_empty() {
+                1.0
+            } else {
+                0.0
+            };
+        }
+
+        let (mut i, mut j, mut dot) = (0, 0, 0.0);
+        while i < first.len() && j < second.len() {
+            match first[i].0.cmp(second[j].0) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    dot += first[i].1 * second[j].1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+
+        dot
+    }
+}
+
+impl ClusterAlgo for Cosine {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.similarity(first, second) > self.ratio
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        1.0 - self.similarity(first, second)
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(1.0 - self.ratio)
+    }
+
+    /// Counts `record` as one more document weighting tokens, so streamed
+    /// records are weighted by the frequencies seen so far.
+    fn observe(&mut self, record: &str) {
+        let tokens = self
+            .tokens(record)
+            .map(|token| token.to_string())
+            .collect::<HashSet<String>>();
+
+        for token in tokens {
+            *self.frequencies.entry(token).or_insert(0) += 1;
+        }
+        self.documents += 1;
+    }
+
+    fn pairs(&self, lines: &[String]) -> Vec<(usize, usize)> {
+        let vectors = lines
+            .par_iter()
+            .map(|line| self.vector(line))
+            .collect::<Vec<Vec<(&str, f64)>>>();
+
+        let mut postings = HashMap::new();
+        for (index, vector) in vectors.iter().enumerate() {
+            for &(token, weight) in vector {
+                postings
+                    .entry(token)
+                    .or_insert_with(Vec::new)
+                    .push((index, weight));
+            }
+        }
+
+        let mut pairs = (0..lines.len())
+            .into_par_iter()
+            .flat_map(|first| {
+                let mut dots = HashMap::new();
+                for &(token, weight) in &vectors[first] {
+                    for &(second, other) in &postings[token] {
+                        if second > first {
+                            *dots.entry(second).or_insert(0.0) += weight * other;
+                        }
+                    }
+                }
+
+                dots.into_iter()
+                    .filter(|&(_, dot)| dot > self.ratio)
+                    .map(|(second, _)| (first, second))
+                    .collect::<Vec<(usize, usize)>>()
+            })
+            .collect::<Vec<(usize, usize)>>();
+
+        let empty = (0..lines.len())
+            .filter(|&i| vectors[i].is_empty())
+            .collect::<Vec<usize>>();
+
+        if 1.0 > self.ratio {
+            for (n, &first) in empty.iter().enumerate() {
+                for &second in &empty[n + 1..] {
+                    pairs.push((first, second));
+                }
+            }
+        }
+
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{brute_force, lines, sorted};
+    use crate::combinations::combinations;
+
+    fn messages() -> Vec<String> {
+        lines(&[
+            "error: could not open config file /etc/app.toml",
+            "error: could not open config file /home/app.toml",
+            "error: could not connect to database",
+            "warning: config file is deprecated",
+            "error: could not connect to database",
+            "",
+            "the and of",
+        ])
+    }
+
+    #[test]
+    fn rare_tokens_weigh_more() {
+        let lines = messages();
+        let algo = Cosine::new(0.5).fit(&lines);
+
+        assert!(algo.similarity(&lines[0], &lines[1]) > algo.similarity(&lines[0], &lines[2]));
+        assert!((algo.similarity(&lines[2], &lines[4]) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stopwords_are_ignored() {
+        let algo = Cosine::new(0.5).stopwords(ENGLISH_STOPWORDS);
+
+        assert!(algo.accept("the cat", "a cat"));
+        assert!(algo.accept("The and Of", ""));
+        assert!(!Cosine::new(0.5).accept("the cat", "a dog"));
+    }
+
+    #[test]
+    fn index_matches_brute_force() {
+        let lines = messages();
+
+        for tokenizer in [Tokenizer::Alphanumeric, Tokenizer::Whitespace]
+            .iter()
+            .cloned()
+        {
+            for ratio in [0.1, 0.35, 0.55, 0.85].iter().cloned() {
+                let algo = Cosine::new(ratio)
+                    .tokenizer(tokenizer)
+                    .stopwords(ENGLISH_STOPWORDS)
+                    .fit(&lines);
+
+                assert_eq!(sorted(algo.pairs(&lines)), brute_force(&algo, &lines));
+            }
+        }
+    }
+
+    #[test]
+    fn observing_matches_fitting() {
+        let lines = messages();
+        let fitted = Cosine::new(0.5).fit(&lines);
+        let mut observed = Cosine::new(0.5).fit(&[]);
+        for line in &lines {
+            observed.observe(line);
+        }
+
+        for (first, second) in combinations(&lines) {
+            assert!(
+                (fitted.similarity(first, second) - observed.similarity(first, second)).abs()
+                    < 1e-9
+            );
+        }
+    }
+}
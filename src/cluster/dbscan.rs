@@ -0,0 +1,129 @@
+use super::ClusterAlgo;
+use rayon::prelude::*;
+
+/// The role of a record in density based clustering.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Point {
+    /// Has at least the minimum number of records within reach, counting itself.
+    Core,
+    /// Within reach of a core record without being one.
+    Border,
+    /// Within reach of no core record, so in no cluster.
+    Noise,
+}
+
+/// Classifies `values` seen `counts` times each by how many records lie within
+/// the accepted `pairs` of them, returning the pairs that join core values to
+/// each other and every border value to its closest core value.
+pub(super) fn classify<A>(
+    algo: &A,
+    values: &[String],
+    counts: &[usize],
+    pairs: Vec<(usize, usize)>,
+    min_points: usize,
+) -> (Vec<(usize, usize)>, Vec<Point>)
+where
+    A: ClusterAlgo,
+{
+    let mut neighbours = vec![Vec::new(); values.len()];
+    for &(first, second) in &pairs {
+        neighbours[first].push(second);
+        neighbours[second].push(first);
+    }
+
+    let core = neighbours
+        .iter()
+        .enumerate()
+        .map(|(value, near)| {
+            let reach = counts[value] + near.iter().map(|&other| counts[other]).sum::<usize>();
+            reach >= min_points
+        })
+        .collect::<Vec<bool>>();
+
+    let borders = (0..values.len())
+        .into_par_iter()
+        .filter(|&value| !core[value])
+        .filter_map(|value| {
+            neighbours[value]
+                .iter()
+                .filter(|&&other| core[other])
+                .map(|&other| (algo.distance(&values[value], &values[other]), other))
+                .min_by(|a, b| {
+                    a.0.partial_cmp(&b.0)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then(a.1.cmp(&b.1))
+                })
+                .map(|(_, other)| (value, other))
+        })
+        .collect::<Vec<(usize, usize)>>();
+
+    let mut points = core
+        .iter()
+        .map(|&core| if core { Point::Core } else { Point::Noise })
+        .collect::<Vec<Point>>();
+    for &(value, _) in &borders {
+        points[value] = Point::Border;
+    }
+
+    let joined = pairs
+        .into_iter()
+        .filter(|&(first, second)| core[first] && core[second])
+        .chain(borders)
+        .collect();
+
+    (joined, points)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{lines, sets};
+    use crate::cluster::{Levenshtein, Singletons};
+
+    #[test]
+    fn noise_does_not_bridge() {
+        let lines = lines(&[
+            "aaaa", "aaab", "aaac", "aaad", "aabb", "abbb", "bbbb", "bbba", "bbbc", "bbbd", "zzzz",
+        ]);
+        let algo = Levenshtein::new(2);
+
+        assert_eq!(algo.cluster(&lines).into_iter().count(), 1);
+
+        let cluster = algo
+            .cluster_dense(&lines, &lines, 4)
+            .singletons(Singletons::Include);
+
+        assert_eq!(
+            sets(cluster),
+            vec![
+                lines[0..5].iter().collect::<Vec<&String>>(),
+                lines[5..10].iter().collect::<Vec<&String>>(),
+            ]
+        );
+    }
+
+    #[test]
+    fn points_count_duplicates() {
+        let lines = lines(&["abc", "abc", "abd", "xyz", "abc", "xbc"]);
+        let cluster = Levenshtein::new(2).cluster_dense(&lines, &lines, 5);
+
+        assert_eq!(
+            (0..lines.len())
+                .map(|i| cluster.point(i))
+                .collect::<Vec<Option<Point>>>(),
+            vec![
+                Some(Point::Core),
+                Some(Point::Core),
+                Some(Point::Border),
+                Some(Point::Noise),
+                Some(Point::Core),
+                Some(Point::Border),
+            ]
+        );
+        assert_eq!(cluster.noise(), vec![3]);
+        assert_eq!(
+            cluster.labels(),
+            vec![Some(0), Some(0), Some(0), None, Some(0), Some(0)]
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use super::disjoint_set::DisjointSet;
+use super::{distinct, expand, Cluster, ClusterAlgo, Linkage};
+use rayon::prelude::*;
+
+/// One step of a [`Dendrogram`], joining two nodes into a new one.
+///
+/// Nodes below the number of leaves are leaves, and node `leaves + k` is the
+/// one formed by the `k`th merge.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Merge {
+    /// The first node joined.
+    pub left: usize,
+    /// The second node joined.
+    pub right: usize,
+    /// The linkage distance between the two nodes.
+    pub height: f64,
+    /// The number of leaves under the new node.
+    pub size: usize,
+    /// A leaf under each of the joined nodes.
+    leaves: (usize, usize),
+}
+
+/// The full agglomeration hierarchy of distinct keys, which can be cut into
+/// flat clusters at any height.
+///
+/// Building it measures [`ClusterAlgo::distance`] between every pair of
+/// distinct keys, so it takes time and memory quadratic in their number.
+pub struct Dendrogram<'a> {
+    records: &'a [String],
+    firsts: Vec<usize>,
+    slots: Vec<usize>,
+    merges: Vec<Merge>,
+}
+
+impl<'a> Dendrogram<'a> {
+    /// Agglomerates `lines` by the distance between their `keys` under `algo`,
+    /// measuring the distance between groups according to `linkage`.
+    ///
+    /// Lines with equal keys share a leaf.
+    pub fn new<A>(
+        algo: &A,
+        lines: &'a [String],
+        keys: &[String],
+        linkage: Linkage,
+    ) -> Dendrogram<'a>
+    where
+        A: ClusterAlgo,
+    {
+        let (values, firsts, slots) = distinct(keys);
+        let mut matrix = Matrix::new(algo, &values);
+        let mut merges = matrix.agglomerate(linkage);
+
+        merges.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal));
+
+        let leaves = values.len();
+        let mut set = DisjointSet::new(leaves);
+        let mut nodes = (0..leaves).collect::<Vec<usize>>();
+        let mut sizes = vec![1; leaves];
+
+        let merges = merges
+            .into_iter()
+            .enumerate()
+            .map(|(k, (first, second, height))| {
+                let (a, b) = (set.find(first), set.find(second));
+                let merge = Merge {
+                    left: nodes[a],
+                    right: nodes[b],
+                    height,
+                    size: sizes[a] + sizes[b],
+                    leaves: (first, second),
+                };
+
+                set.union(a, b);
+                let root = set.find(a);
+                nodes[root] = leaves + k;
+                sizes[root] = merge.size;
+
+                merge
+            })
+            .collect();
+
+        Dendrogram {
+            records: lines,
+            firsts,
+            slots,
+            merges,
+        }
+    }
+
+    /// The number of leaves, one per distinct key.
+    pub fn leaves(&self) -> usize {
+        self.firsts.len()
+    }
+
+    /// The first line with the key of `leaf`.
+    pub fn leaf(&self, leaf: usize) -> &'a String {
+        &self.records[self.firsts[leaf]]
+    }
+
+    /// The number of lines with the key of each leaf.
+    pub fn counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.leaves()];
+        for &slot in &self.slots {
+            counts[slot] += 1;
+        }
+
+        counts
+    }
+
+    /// Every merge, lowest first.
+    pub fn merges(&self) -> &[Merge] {
+        &self.merges
+    }
+
+    /// Flat clusters joined by the merges below `height`.
+    pub fn cut(&self, height: f64) -> Cluster<'a> {
+        let pairs = self
+            .merges
+            .iter()
+            .take_while(|merge| merge.height < height)
+            .map(|merge| merge.leaves)
+            .collect();
+
+        Cluster::from_pairs(self.records, expand(&self.firsts, &self.slots, pairs))
+    }
+}
+
+/// Distances between groups of distinct keys, indexed by a leaf of each group.
+struct Matrix {
+    len: usize,
+    distances: Vec<f64>,
+}
+
+impl Matrix {
+    fn new<A>(algo: &A, values: &[String]) -> Matrix
+    where
+        A: ClusterAlgo,
+    {
+        let len = values.len();
+        let distances = (0..len)
+            .into_par_iter()
+            .flat_map(|i| {
+                (i + 1..len)
+                    .map(|j| algo.distance(&values[i], &values[j]))
+                    .collect::<Vec<f64>>()
+            })
+            .collect();
+
+        Matrix { len, distances }
+    }
+
+    fn index(&self, first: usize, second: usize) -> usize {
+        let (i, j) = (first.min(second), first.max(second));
+        self.len * i - i * (i + 1) / 2 + j - i - 1
+    }
+
+    fn get(&self, first: usize, second: usize) -> f64 {
+        self.distances[self.index(first, second)]
+    }
+
+    fn set(&mut self, first: usize, second: usize, distance: f64) {
+        let index = self.index(first, second);
+        self.distances[index] = distance;
+    }
+
+    /// Merges every group by following chains of nearest neighbours, each
+    /// merge naming a leaf of both groups and their distance.
+    fn agglomerate(&mut self, linkage: Linkage) -> Vec<(usize, usize, f64)> {
+        let mut active = vec![true; self.len];
+        let mut sizes = vec![1usize; self.len];
+        let mut merges = Vec::new();
+        let mut chain = Vec::new();
+
+        for _ in 1..self.len {
+            if chain.is_empty() {
+                chain.push(active.iter().position(|&a| a).unwrap());
+            }
+
+            let (first, second) = loop {
+                let cur = chain[chain.len() - 1];
+                let prev = if chain.len() > 1 {
+                    Some(chain[chain.len() - 2])
+                } else {
+                    None
+                };
+
+                let mut nearest = prev.map(|prev| (self.get(cur, prev), prev));
+                for other in (0..self.len).filter(|&other| active[other] && other != cur) {
+                    let distance = self.get(cur, other);
+                    if nearest.is_none_or(|(best, _)| distance < best) {
+                        nearest = Some((distance, other));
+                    }
+                }
+
+                let (_, next) = nearest.unwrap();
+                if Some(next) == prev {
+                    chain.pop();
+                    chain.pop();
+                    break (cur, next);
+                }
+
+                chain.push(next);
+            };
+
+            let height = self.get(first, second);
+            for other in (0..self.len).filter(|&other| active[other]) {
+                if other == first || other == second {
+                    continue;
+                }
+
+                let (a, b) = (self.get(first, other), self.get(second, other));
+                let distance = match linkage {
+                    Linkage::Single => a.min(b),
+                    Linkage::Complete => a.max(b),
+                    Linkage::Average => {
+                        let (x, y) = (sizes[first] as f64, sizes[second] as f64);
+                        (x * a + y * b) / (x + y)
+                    }
+                };
+
+                self.set(second, other, distance);
+            }
+
+            active[first] = false;
+            sizes[second] += sizes[first];
+            merges.push((first, second, height));
+        }
+
+        merges
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{lines, sets};
+    use crate::cluster::Levenshtein;
+
+    #[test]
+    fn cuts_match_thresholds() {
+        let lines = lines(&["aaaa", "aaab", "aabb", "abbb", "bbbb", "aaab", "zzzzzzzz"]);
+
+        for linkage in [Linkage::Single, Linkage::Complete, Linkage::Average]
+            .iter()
+            .cloned()
+        {
+            let dendrogram = Dendrogram::new(&Levenshtein::new(1), &lines, &lines, linkage);
+            assert_eq!(dendrogram.leaves(), 6);
+            assert_eq!(dendrogram.merges().len(), 5);
+            assert_eq!(dendrogram.merges()[4].size, 6);
+            assert_eq!(dendrogram.counts(), vec![1, 2, 1, 1, 1, 1]);
+
+            assert_eq!(
+                sets(dendrogram.cut(1.0)),
+                vec![vec![&lines[1]]],
+                "{:?}",
+                linkage
+            );
+        }
+
+        let dendrogram = Dendrogram::new(&Levenshtein::new(1), &lines, &lines, Linkage::Single);
+        for threshold in 0..10 {
+            assert_eq!(
+                sets(dendrogram.cut(threshold as f64)),
+                sets(Levenshtein::new(threshold).cluster(&lines))
+            );
+        }
+    }
+
+    #[test]
+    fn complete_linkage_heights() {
+        let lines = lines(&["aaaa", "aaab", "aabb", "abbb", "bbbb"]);
+        let dendrogram = Dendrogram::new(&Levenshtein::new(1), &lines, &lines, Linkage::Complete);
+        let heights = dendrogram
+            .merges()
+            .iter()
+            .map(|merge| merge.height)
+            .collect::<Vec<f64>>();
+
+        assert_eq!(heights, vec![1.0, 1.0, 2.0, 4.0]);
+        assert_eq!(
+            dendrogram.merges()[3],
+            Merge {
+                left: 7,
+                right: 5,
+                height: 4.0,
+                size: 5,
+                leaves: dendrogram.merges()[3].leaves,
+            }
+        );
+    }
+}
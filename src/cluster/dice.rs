@@ -0,0 +1,44 @@
+use super::ClusterAlgo;
+use strsim::sorensen_dice;
+
+/// Clusters records whose sørensen-dice bigram similarity exceeds a ratio.
+///
+/// Whitespace is ignored, so reordered words still share most bigrams.
+pub struct Dice {
+    ratio: f64,
+}
+
+impl Dice {
+    /// Accepts pairs with a bigram similarity above `ratio`.
+    pub fn new(ratio: f64) -> Dice {
+        Dice { ratio }
+    }
+}
+
+impl ClusterAlgo for Dice {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        sorensen_dice(first, second) > self.ratio
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        1.0 - sorensen_dice(first, second)
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(1.0 - self.ratio)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::NormLevenshtein;
+
+    #[test]
+    fn tolerates_reordering() {
+        let (first, second) = ("Apple iPhone 11 Pro", "iPhone 11 Pro Apple");
+
+        assert!(Dice::new(0.7).accept(first, second));
+        assert!(!NormLevenshtein::new(0.7).accept(first, second));
+    }
+}
@@ -0,0 +1,87 @@
+/// Union-find over the indices `0..len`.
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+}
+
+impl DisjointSet {
+    pub fn new(len: usize) -> DisjointSet {
+        DisjointSet {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+        }
+    }
+
+    pub fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        let mut cur = index;
+        while self.parent[cur] != root {
+            let next = self.parent[cur];
+            self.parent[cur] = root;
+            cur = next;
+        }
+
+        root
+    }
+
+    pub fn union(&mut self, first: usize, second: usize) {
+        let first = self.find(first);
+        let second = self.find(second);
+
+        if first == second {
+            return;
+        }
+
+        if self.rank[first] < self.rank[second] {
+            self.parent[first] = second;
+        } else if self.rank[first] > self.rank[second] {
+            self.parent[second] = first;
+        } else {
+            self.parent[second] = first;
+            self.rank[first] += 1;
+        }
+    }
+
+    /// Every set, ordered by its smallest index, with members in ascending order.
+    pub fn sets(mut self) -> Vec<Vec<usize>> {
+        let mut slots = vec![None; self.parent.len()];
+        let mut sets: Vec<Vec<usize>> = Vec::new();
+
+        for index in 0..self.parent.len() {
+            let root = self.find(index);
+            let slot = match slots[root] {
+                Some(slot) => slot,
+                None => {
+                    slots[root] = Some(sets.len());
+                    sets.push(Vec::new());
+                    sets.len() - 1
+                }
+            };
+
+            sets[slot].push(index);
+        }
+
+        sets
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unions_are_transitive() {
+        let mut set = DisjointSet::new(6);
+        set.union(0, 3);
+        set.union(4, 3);
+        set.union(1, 5);
+
+        assert_eq!(set.find(0), set.find(4));
+        assert_ne!(set.find(0), set.find(1));
+        assert_eq!(set.sets(), vec![vec![0, 3, 4], vec![1, 5], vec![2]]);
+    }
+}
@@ -0,0 +1,118 @@
+use super::{ClusterAlgo, Metric};
+use strsim::hamming;
+
+/// How records of different lengths are compared.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Unequal {
+    /// Records of different lengths are infinitely far apart.
+    Infinite,
+    /// The shorter record is padded, each missing character counting as a substitution.
+    Pad,
+}
+
+/// Clusters fixed width records within a hamming distance.
+pub struct Hamming {
+    threshold: usize,
+    unequal: Unequal,
+}
+
+impl Hamming {
+    /// Accepts equal length pairs with fewer than `threshold` substitutions.
+    pub fn new(threshold: usize) -> Hamming {
+        Hamming {
+            threshold,
+            unequal: Unequal::Infinite,
+        }
+    }
+
+    /// Compare records of different lengths according to `unequal`.
+    pub fn unequal(mut self, unequal: Unequal) -> Hamming {
+        self.unequal = unequal;
+        self
+    }
+
+    fn substitutions(&self, first: &str, second: &str) -> Option<usize> {
+        match (hamming(first, second), self.unequal) {
+            (Ok(dist), _) => Some(dist),
+            (Err(_), Unequal::Infinite) => None,
+            (Err(_), Unequal::Pad) => Some(padded(first, second)),
+        }
+    }
+}
+
+fn padded(first: &str, second: &str) -> usize {
+    let shared = first
+        .chars()
+        .zip(second.chars())
+        .filter(|(a, b)| a != b)
+        .count();
+
+    let (first, second) = (first.chars().count(), second.chars().count());
+
+    shared + first.max(second) - first.min(second)
+}
+
+impl ClusterAlgo for Hamming {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        match self.substitutions(first, second) {
+            Some(dist) => dist < self.threshold,
+            None => false,
+        }
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        match self.substitutions(first, second) {
+            Some(dist) => dist as f64,
+            None => f64::INFINITY,
+        }
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.threshold as f64)
+    }
+}
+
+/// Padded distance bounds both policies, as accepted pairs under
+/// [`Unequal::Infinite`] always have equal lengths.
+impl Metric for Hamming {
+    fn metric(&self, first: &str, second: &str) -> usize {
+        padded(first, second)
+    }
+
+    fn radius(&self) -> Option<usize> {
+        self.threshold.checked_sub(1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{brute_force, lines, sorted};
+    use crate::cluster::BkTree;
+
+    #[test]
+    fn unequal_lengths() {
+        assert!(Hamming::new(2).accept("SKU-1234", "SKU-1284"));
+        assert!(!Hamming::new(2).accept("SKU-1234", "SKU-12345"));
+        assert!(Hamming::new(2)
+            .unequal(Unequal::Pad)
+            .accept("SKU-1234", "SKU-12345"));
+        assert!(!Hamming::new(2)
+            .unequal(Unequal::Pad)
+            .accept("SKU-1234", "SKU-12845"));
+    }
+
+    #[test]
+    fn bk_tree_matches_brute_force() {
+        let lines = lines(&["0000", "0001", "0011", "00011", "1111", "000", "0001"]);
+
+        for unequal in [Unequal::Infinite, Unequal::Pad].iter().cloned() {
+            for threshold in 0..4 {
+                let algo = Hamming::new(threshold).unequal(unequal);
+                let expected = brute_force(&algo, &lines);
+
+                assert_eq!(sorted(BkTree::new(algo).pairs(&lines)), expected);
+            }
+        }
+    }
+}
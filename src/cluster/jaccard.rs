@@ -0,0 +1,238 @@
+use super::ClusterAlgo;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Clusters records whose character q-gram sets have a jaccard similarity
+/// above a ratio.
+///
+/// Records shorter than `q` characters are a single gram. Pairs are found with
+/// a prefix filter: a record of `n` grams shares at least `ceil(ratio * n)` of
+/// them with any record it matches, so indexing just its `n - ceil(ratio * n) + 1`
+/// rarest grams is enough to meet every match.
+pub struct Jaccard {
+    ratio: f64,
+    q: usize,
+}
+
+impl Jaccard {
+    /// Accepts pairs of bigram sets with a jaccard similarity above `ratio`.
+    pub fn new(ratio: f64) -> Jaccard {
+        Jaccard { ratio, q: 2 }
+    }
+
+    /// Compare grams of `q` characters, at least one.
+    pub fn q(mut self, q: usize) -> Jaccard {
+        self.q = q.max(1);
+        self
+    }
+
+    fn grams<'a>(&self, record: &'a str) -> HashSet<&'a str> {
+        if record.is_empty() {
+            return HashSet::new();
+        }
+
+        let bounds = record
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(Some(record.len()))
+            .collect::<Vec<usize>>();
+
+        let q = self.q.min(bounds.len() - 1);
+        bounds
+            .windows(q + 1)
+            .map(|window| &record[window[0]..window[q]])
+            .collect()
+    }
+
+    fn similarity(&self, first: &str, second: &str) -> f64 {
+        let first = self.grams(first);
+        let second = self.grams(second);
+
+        jaccard(
+            first.intersection(&second).count(),
+            first.len(),
+            second.len(),
+        )
+    }
+}
+
+fn jaccard(shared: usize, first: usize, second: usize) -> f64 {
+    let union = first + second - shared;
+    if union == 0 {
+        1.0
+    } else {
+        shared as f64 / union as f64
+    }
+}
+
+impl ClusterAlgo for Jaccard {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.similarity(first, second) > self.ratio
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        1.0 - self.similarity(first, second)
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(1.0 - self.ratio)
+    }
+
+    fn pairs(&self, lines: &[String]) -> Vec<(usize, usize)> {
+        let mut ids = HashMap::new();
+        let mut grams = lines
+            .iter()
+            .map(|line| {
+                self.grams(line)
+                    .into_iter()
+                    .map(|gram| {
+                        let next = ids.len();
+                        *ids.entry(gram).or_insert(next)
+                    })
+                    .collect::<Vec<usize>>()
+            })
+            .collect::<Vec<Vec<usize>>>();
+
+        // Order grams rarest first, so the prefixes below hold the most selective grams.
+        let mut frequency = vec![0usize; ids.len()];
+        for gram in grams.iter().flatten() {
+            frequency[*gram] += 1;
+        }
+
+        let mut order = (0..ids.len()).collect::<Vec<usize>>();
+        order.sort_unstable_by_key(|&gram| (frequency[gram], gram));
+
+        let mut rank = vec![0; ids.len()];
+        for (position, &gram) in order.iter().enumerate() {
+            rank[gram] = position;
+        }
+
+        for grams in &mut grams {
+            for gram in grams.iter_mut() {
+                *gram = rank[*gram];
+            }
+            grams.sort_unstable();
+        }
+
+        // Pairs above the ratio share at least `ratio * len` grams, so they
+        // must share one of the first `len - ratio * len + 1` grams of each.
+        let prefix = |len: usize| {
+            let shared = ((self.ratio * len as f64) - 1e-9).ceil().max(1.0) as usize;
+            (len + 1).saturating_sub(shared).min(len)
+        };
+
+        let mut postings = vec![Vec::new(); ids.len()];
+        for (index, grams) in grams.iter().enumerate() {
+            for &gram in &grams[..prefix(grams.len())] {
+                postings[gram].push(index);
+            }
+        }
+
+        let mut pairs = (0..lines.len())
+            .into_par_iter()
+            .flat_map(|first| {
+                let a = grams[first].len();
+                let candidates = grams[first][..prefix(a)]
+                    .iter()
+                    .flat_map(|&gram| postings[gram].iter().cloned())
+                    .filter(|&second| second > first)
+                    .collect::<HashSet<usize>>();
+
+                candidates
+                    .into_iter()
+                    .filter(|&second| {
+                        let b = grams[second].len();
+                        let shared = overlap(&grams[first], &grams[second]);
+
+                        jaccard(shared, a, b) > self.ratio
+                    })
+                    .map(|second| (first, second))
+                    .collect::<Vec<(usize, usize)>>()
+            })
+            .collect::<Vec<(usize, usize)>>();
+
+        let empty = (0..lines.len())
+            .filter(|&i| grams[i].is_empty())
+            .collect::<Vec<usize>>();
+
+        if 1.0 > self.ratio {
+            for (n, &first) in empty.iter().enumerate() {
+                for &second in &empty[n + 1..] {
+                    pairs.push((first, second));
+                }
+            }
+        }
+
+        pairs
+    }
+}
+
+/// Number of values in both sorted slices.
+fn overlap(first: &[usize], second: &[usize]) -> usize {
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < first.len() && j < second.len() {
+        match first[i].cmp(&second[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    shared
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{brute_force, lines, sorted};
+
+    #[test]
+    fn grams() {
+        let algo = Jaccard::new(0.5).q(3);
+
+        let mut grams = algo.grams("héllo").into_iter().collect::<Vec<&str>>();
+        grams.sort();
+
+        assert_eq!(grams, vec!["hél", "llo", "éll"]);
+        assert_eq!(algo.grams("hi").into_iter().collect::<Vec<_>>(), vec!["hi"]);
+        assert!(algo.grams("").is_empty());
+    }
+
+    #[test]
+    fn index_matches_brute_force() {
+        let lines = lines(&[
+            "night",
+            "nacht",
+            "nightly",
+            "knight",
+            "",
+            "",
+            "a",
+            "a",
+            "ab",
+            "abab",
+            "hello world",
+            "world hello",
+            "héllo",
+            "hello",
+        ]);
+
+        for &ratio in &[0.0, 0.2, 0.4, 0.5, 0.6, 0.8, 0.99] {
+            for q in 1..4 {
+                let algo = Jaccard::new(ratio).q(q);
+
+                assert_eq!(
+                    sorted(algo.pairs(&lines)),
+                    brute_force(&algo, &lines),
+                    "ratio {} q {}",
+                    ratio,
+                    q
+                );
+            }
+        }
+    }
+}
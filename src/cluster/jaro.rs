@@ -1,27 +1,57 @@
 use super::ClusterAlgo;
-use clap::ArgMatches;
 use strsim::{jaro, jaro_winkler};
 
+/// Clusters records whose jaro (or jaro winkler) similarity exceeds a ratio.
 pub struct Jaro {
     ratio: f64,
     winkle: bool,
 }
 
-impl ClusterAlgo for Jaro {
-    fn from_matches(matches: &ArgMatches) -> Self {
-        let ratio = matches.value_of("ratio").unwrap().parse::<f64>().unwrap();
-        let winkle = matches.is_present("winkler");
+impl Jaro {
+    /// Accepts pairs with a jaro similarity above `ratio`.
+    pub fn new(ratio: f64) -> Jaro {
+        Jaro {
+            ratio,
+            winkle: false,
+        }
+    }
 
-        Jaro { ratio, winkle }
+    /// Use jaro winkler, favouring records with a common prefix.
+    pub fn winkler(mut self, winkle: bool) -> Jaro {
+        self.winkle = winkle;
+        self
     }
 
-    fn accept<'a>(&self, first: &'a String, second: &'a String) -> bool {
-        let sim = if self.winkle {
+    fn similarity(&self, first: &str, second: &str) -> f64 {
+        if self.winkle {
             jaro_winkler(first, second)
         } else {
             jaro(first, second)
-        };
+        }
+    }
+}
+
+impl ClusterAlgo for Jaro {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.similarity(first, second) > self.ratio
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        1.0 - self.similarity(first, second)
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(1.0 - self.ratio)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
 
-        sim > self.ratio
+    #[test]
+    fn winkler_favours_prefix() {
+        assert!(!Jaro::new(0.87).accept("prefixed", "prefaced"));
+        assert!(Jaro::new(0.87).winkler(true).accept("prefixed", "prefaced"));
     }
 }
@@ -0,0 +1,89 @@
+use super::ClusterAlgo;
+use rayon::prelude::*;
+
+/// Clusters records one at a time, for input that never ends.
+///
+/// Every cluster is led by the first record that started it. Each new record
+/// is compared only to the leaders and joins the closest one that accepts it,
+/// or leads a new cluster if none does. Assignments are final, so results
+/// depend on input order.
+pub struct Leader<A> {
+    algo: A,
+    leaders: Vec<String>,
+    sizes: Vec<usize>,
+}
+
+impl<A> Leader<A>
+where
+    A: ClusterAlgo,
+{
+    /// Starts without clusters, accepting leaders with `algo`.
+    pub fn new(algo: A) -> Leader<A> {
+        Leader {
+            algo,
+            leaders: Vec::new(),
+            sizes: Vec::new(),
+        }
+    }
+
+    /// The id of the cluster `record` joins, ties going to the earliest leader.
+    ///
+    /// The algorithm [observes](ClusterAlgo::observe) `record` first.
+    pub fn assign(&mut self, record: &str) -> usize {
+        self.algo.observe(record);
+
+        let algo = &self.algo;
+        let closest = self
+            .leaders
+            .par_iter()
+            .enumerate()
+            .filter(|(_, leader)| algo.accept(record, leader))
+            .map(|(id, leader)| (algo.distance(record, leader), id))
+            .min_by(|a, b| {
+                a.0.partial_cmp(&b.0)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.1.cmp(&b.1))
+            });
+
+        match closest {
+            Some((_, id)) => {
+                self.sizes[id] += 1;
+                id
+            }
+            None => {
+                self.leaders.push(record.to_string());
+                self.sizes.push(1);
+                self.leaders.len() - 1
+            }
+        }
+    }
+
+    /// The record leading cluster `id`.
+    pub fn leader(&self, id: usize) -> &str {
+        &self.leaders[id]
+    }
+
+    /// The number of records assigned to cluster `id` so far.
+    pub fn size(&self, id: usize) -> usize {
+        self.sizes[id]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::Levenshtein;
+
+    #[test]
+    fn records_join_closest_leader() {
+        let mut leader = Leader::new(Levenshtein::new(3));
+        let ids = ["abcd", "wxyz", "abcz", "wbcz", "wxyd", "qqqq"]
+            .iter()
+            .map(|record| leader.assign(record))
+            .collect::<Vec<usize>>();
+
+        assert_eq!(ids, vec![0, 1, 0, 0, 1, 2]);
+        assert_eq!(leader.leader(2), "qqqq");
+        assert_eq!((leader.size(0), leader.size(1), leader.size(2)), (3, 2, 1));
+    }
+}
@@ -1,31 +1,73 @@
-use super::ClusterAlgo;
-use clap::ArgMatches;
+use super::{ClusterAlgo, Metric};
 use strsim::{damerau_levenshtein, levenshtein};
 
+/// Clusters records within a levenshtein (or damerau levenshtein) edit distance.
 pub struct Levenshtein {
     threshold: usize,
     damerau: bool,
 }
 
-impl ClusterAlgo for Levenshtein {
-    fn from_matches(matches: &ArgMatches) -> Self {
-        let threshold = matches
-            .value_of("threshold")
-            .unwrap()
-            .parse::<usize>()
-            .unwrap();
-        let damerau = matches.is_present("damerau");
+impl Levenshtein {
+    /// Accepts pairs with an edit distance below `threshold`.
+    pub fn new(threshold: usize) -> Levenshtein {
+        Levenshtein {
+            threshold,
+            damerau: false,
+        }
+    }
 
-        Levenshtein { threshold, damerau }
+    /// Count adjacent transpositions as a single edit.
+    pub fn damerau(mut self, damerau: bool) -> Levenshtein {
+        self.damerau = damerau;
+        self
     }
 
-    fn accept<'a>(&self, first: &'a String, second: &'a String) -> bool {
-        let sim = if self.damerau {
+    fn edits(&self, first: &str, second: &str) -> usize {
+        if self.damerau {
             damerau_levenshtein(first, second)
         } else {
             levenshtein(first, second)
-        };
+        }
+    }
+}
+
+impl ClusterAlgo for Levenshtein {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.edits(first, second) < self.threshold
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        self.edits(first, second) as f64
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.threshold as f64)
+    }
+}
+
+impl Metric for Levenshtein {
+    fn metric(&self, first: &str, second: &str) -> usize {
+        self.edits(first, second)
+    }
+
+    fn radius(&self) -> Option<usize> {
+        self.threshold.checked_sub(1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn threshold_is_exclusive() {
+        assert!(Levenshtein::new(2).accept("hello", "hallo"));
+        assert!(!Levenshtein::new(2).accept("world", "words"));
+    }
 
-        sim < self.threshold
+    #[test]
+    fn damerau_transposition() {
+        assert!(!Levenshtein::new(2).accept("abcd", "abdc"));
+        assert!(Levenshtein::new(2).damerau(true).accept("abcd", "abdc"));
     }
 }
@@ -0,0 +1,154 @@
+use super::disjoint_set::DisjointSet;
+use super::ClusterAlgo;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::mem;
+
+/// How accepted pairs decide which groups are merged.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Linkage {
+    /// Any accepted pair joins two groups, so chains of pairs form one group.
+    Single,
+    /// Groups join only if every pair across them is accepted.
+    Complete,
+    /// Groups join if their mean distance passes [`ClusterAlgo::cutoff`], or
+    /// without a cutoff if most pairs across them are accepted.
+    Average,
+}
+
+/// Agglomerates `values` along accepted `pairs`, closest first, returning the
+/// pairs that joined two groups.
+pub(super) fn merge<A>(
+    algo: &A,
+    values: &[String],
+    pairs: Vec<(usize, usize)>,
+    linkage: Linkage,
+) -> Vec<(usize, usize)>
+where
+    A: ClusterAlgo,
+{
+    if linkage == Linkage::Single {
+        return pairs;
+    }
+
+    let mut pairs = pairs
+        .into_par_iter()
+        .map(|(first, second)| {
+            (
+                algo.distance(&values[first], &values[second]),
+                first,
+                second,
+            )
+        })
+        .collect::<Vec<(f64, usize, usize)>>();
+    pairs.sort_by(|a, b| {
+        a.0.partial_cmp(&b.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then((a.1, a.2).cmp(&(b.1, b.2)))
+    });
+
+    let mut groups = Groups::new(values.len());
+    for &(_, first, second) in &pairs {
+        groups.link(first, second);
+    }
+
+    let mut joined = Vec::new();
+    for (_, first, second) in pairs {
+        let (a, b) = (groups.set.find(first), groups.set.find(second));
+        if a == b {
+            continue;
+        }
+
+        let across = groups.members[a].len() * groups.members[b].len();
+        let accepted = groups.links[a].get(&b).cloned().unwrap_or(0);
+
+        let join = match linkage {
+            Linkage::Single => true,
+            Linkage::Complete => accepted == across,
+            Linkage::Average => match algo.cutoff() {
+                Some(cutoff) => {
+                    let total = groups.members[a]
+                        .par_iter()
+                        .map(|&i| {
+                            groups.members[b]
+                                .iter()
+                                .map(|&j| algo.distance(&values[i], &values[j]))
+                                .sum::<f64>()
+                        })
+                        .sum::<f64>();
+
+                    total / (across as f64) < cutoff
+                }
+                None => 2 * accepted > across,
+            },
+        };
+
+        if join {
+            groups.join(a, b);
+            joined.push((first, second));
+        }
+    }
+
+    joined
+}
+
+/// Groups being merged, with the number of accepted pairs between each two.
+struct Groups {
+    set: DisjointSet,
+    members: Vec<Vec<usize>>,
+    links: Vec<HashMap<usize, usize>>,
+}
+
+impl Groups {
+    fn new(len: usize) -> Groups {
+        Groups {
+            set: DisjointSet::new(len),
+            members: (0..len).map(|i| vec![i]).collect(),
+            links: vec![HashMap::new(); len],
+        }
+    }
+
+    fn link(&mut self, first: usize, second: usize) {
+        *self.links[first].entry(second).or_insert(0) += 1;
+        *self.links[second].entry(first).or_insert(0) += 1;
+    }
+
+    fn join(&mut self, first: usize, second: usize) {
+        self.set.union(first, second);
+        let root = self.set.find(first);
+        let other = if root == first { second } else { first };
+
+        let members = mem::take(&mut self.members[other]);
+        self.members[root].extend(members);
+
+        let links = mem::take(&mut self.links[other]);
+        for (group, count) in links {
+            self.links[group].remove(&other);
+            if group != root {
+                *self.links[group].entry(root).or_insert(0) += count;
+                *self.links[root].entry(group).or_insert(0) += count;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{lines, sorted};
+    use crate::cluster::Levenshtein;
+
+    #[test]
+    fn linkage_breaks_chains() {
+        let values = lines(&["aaaa", "aaab", "aabb", "abbb", "bbbb"]);
+        let algo = Levenshtein::new(2);
+        let joined = |linkage| sorted(merge(&algo, &values, algo.pairs(&values), linkage));
+
+        assert_eq!(
+            joined(Linkage::Single),
+            vec![(0, 1), (1, 2), (2, 3), (3, 4)]
+        );
+        assert_eq!(joined(Linkage::Complete), vec![(0, 1), (2, 3)]);
+        assert_eq!(joined(Linkage::Average), vec![(0, 1), (1, 2), (3, 4)]);
+    }
+}
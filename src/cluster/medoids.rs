@@ -0,0 +1,421 @@
+use super::min_hash::mix;
+use super::{distinct, expand, Cluster, ClusterAlgo};
+use rayon::prelude::*;
+
+/// Splits records into a fixed number of clusters around medoids, the members
+/// with the least total distance to the rest of their cluster.
+///
+/// Medoids are found by PAM over the distinct keys, measuring
+/// [`ClusterAlgo::distance`] between every pair of them. Inputs with more
+/// distinct keys than the sample size are handled as in CLARA: PAM runs on
+/// several samples, each seeded with the best medoids so far, and the medoids
+/// closest to all of the keys win.
+pub struct KMedoids {
+    k: usize,
+    samples: usize,
+    sample_size: Option<usize>,
+}
+
+impl KMedoids {
+    /// Finds `k` clusters, or one per distinct key if there are fewer.
+    pub fn new(k: usize) -> KMedoids {
+        KMedoids {
+            k: k.max(1),
+            samples: 5,
+            sample_size: None,
+        }
+    }
+
+    /// Runs PAM on `samples` samples of large inputs.
+    pub fn samples(mut self, samples: usize) -> KMedoids {
+        self.samples = samples.max(1);
+        self
+    }
+
+    /// Samples `sample_size` distinct keys at a time, defaulting to the larger
+    /// of `40 + 2k` and 500.
+    pub fn sample_size(mut self, sample_size: usize) -> KMedoids {
+        self.sample_size = Some(sample_size.max(self.k));
+        self
+    }
+
+    /// Clusters `lines` around medoids of `keys` under `algo`, choosing each
+    /// medoid as its cluster's representative.
+    ///
+    /// Lines with equal keys share a cluster and count once per line towards
+    /// the distances a medoid minimizes.
+    pub fn cluster<'a, A>(&self, algo: &A, lines: &'a [String], keys: &[String]) -> Cluster<'a>
+    where
+        A: ClusterAlgo,
+    {
+        let (values, firsts, slots) = distinct(keys);
+        let mut weights = vec![0; values.len()];
+        for &slot in &slots {
+            weights[slot] += 1;
+        }
+
+        let medoids = self.medoids(algo, &values, &weights);
+        let pairs = (0..values.len())
+            .into_par_iter()
+            .filter(|value| !medoids.contains(value))
+            .map(|value| (value, nearest(algo, &values, &medoids, value).1))
+            .collect();
+
+        let mut cluster = Cluster::from_pairs(lines, expand(&firsts, &slots, pairs));
+        cluster.representatives = medoids.iter().map(|&medoid| firsts[medoid]).collect();
+        cluster
+    }
+
+    fn medoids<A>(&self, algo: &A, values: &[String], weights: &[usize]) -> Vec<usize>
+    where
+        A: ClusterAlgo,
+    {
+        let k = self.k.min(values.len());
+        let sample_size = self.sample_size.unwrap_or_else(|| (40 + 2 * k).max(500));
+
+        if values.len() <= sample_size {
+            let all = (0..values.len()).collect::<Vec<usize>>();
+            return Pam::new(algo, values, weights, all).run(k);
+        }
+
+        let mut best: Option<(Cost, Vec<usize>)> = None;
+        let mut state = 0;
+        for _ in 0..self.samples {
+            let mut sample = best
+                .as_ref()
+                .map_or_else(Vec::new, |(_, medoids)| medoids.clone());
+
+            let mut rest = (0..values.len())
+                .filter(|value| !sample.contains(value))
+                .collect::<Vec<usize>>();
+            while sample.len() < sample_size {
+                state += 1;
+                let pick = (mix(state) % rest.len() as u64) as usize;
+                sample.push(rest.swap_remove(pick));
+            }
+
+            let medoids = Pam::new(algo, values, weights, sample).run(k);
+            let cost = (0..values.len())
+                .into_par_iter()
+                .map(|value| {
+                    Cost::of(
+                        weights[value] as f64,
+                        nearest(algo, values, &medoids, value).0,
+                    )
+                })
+                .reduce(Cost::default, |a, b| a + b);
+
+            if best.as_ref().is_none_or(|(lowest, _)| cost < *lowest) {
+                best = Some((cost, medoids));
+            }
+        }
+
+        best.map(|(_, medoids)| medoids).unwrap_or_default()
+    }
+}
+
+/// The distance from `value` to its closest medoid and that medoid, ties going
+/// to the earlier medoid.
+fn nearest<A>(algo: &A, values: &[String], medoids: &[usize], value: usize) -> (f64, usize)
+where
+    A: ClusterAlgo,
+{
+    medoids
+        .iter()
+        .map(|&medoid| (algo.distance(&values[value], &values[medoid]), medoid))
+        .fold((f64::INFINITY, medoids[0]), |best, next| {
+            if next.0 < best.0 {
+                next
+            } else {
+                best
+            }
+        })
+}
+
+/// A weighted total of distances, split into the weight of points at an
+/// infinite distance and the sum of the finite ones, and ordered by the
+/// former first so unreachable points never make totals incomparable.
+#[derive(Clone, Copy, PartialEq, PartialOrd, Default, Debug)]
+struct Cost(f64, f64);
+
+impl Cost {
+    fn of(weight: f64, distance: f64) -> Cost {
+        if distance.is_finite() {
+            Cost(0.0, weight * distance)
+        } else {
+            Cost(weight, 0.0)
+        }
+    }
+
+    /// The change in cost of a point of `weight` moving from distance `from`
+    /// to distance `to`.
+    fn change(weight: f64, from: f64, to: f64) -> Cost {
+        let (from, to) = (Cost::of(weight, from), Cost::of(weight, to));
+        Cost(to.0 - from.0, to.1 - from.1)
+    }
+
+    /// Whether this change lowers a cost by more than rounding error.
+    fn lowers(self) -> bool {
+        self.0 < 0.0 || (self.0 == 0.0 && self.1 < -1e-9)
+    }
+
+    fn cmp(&self, other: &Cost) -> std::cmp::Ordering {
+        self.partial_cmp(other).unwrap_or(std::cmp::Ordering::Equal)
+    }
+}
+
+impl std::ops::Add for Cost {
+    type Output = Cost;
+
+    fn add(self, other: Cost) -> Cost {
+        Cost(self.0 + other.0, self.1 + other.1)
+    }
+}
+
+impl std::iter::Sum for Cost {
+    fn sum<I: Iterator<Item = Cost>>(iter: I) -> Cost {
+        iter.fold(Cost::default(), |a, b| a + b)
+    }
+}
+
+/// Partitioning around medoids over a set of weighted keys.
+struct Pam {
+    points: Vec<usize>,
+    weights: Vec<f64>,
+    distances: Vec<f64>,
+}
+
+impl Pam {
+    fn new<A>(algo: &A, values: &[String], weights: &[usize], points: Vec<usize>) -> Pam
+    where
+        A: ClusterAlgo,
+    {
+        let distances = points
+            .par_iter()
+            .flat_map(|&i| {
+                points
+                    .iter()
+                    .map(|&j| {
+                        if i == j {
+                            0.0
+                        } else {
+                            algo.distance(&values[i], &values[j])
+                        }
+                    })
+                    .collect::<Vec<f64>>()
+            })
+            .collect();
+
+        let weights = points.iter().map(|&i| weights[i] as f64).collect();
+
+        Pam {
+            points,
+            weights,
+            distances,
+        }
+    }
+
+    fn distance(&self, first: usize, second: usize) -> f64 {
+        self.distances[first * self.points.len() + second]
+    }
+
+    /// Greedily builds `k` medoids then swaps medoids for other points while
+    /// that lowers the total distance, returning the medoids as values.
+    fn run(&self, k: usize) -> Vec<usize> {
+        let len = self.points.len();
+        let mut medoids = Vec::with_capacity(k);
+        let mut closest = vec![f64::INFINITY; len];
+
+        while medoids.len() < k.min(len) {
+            let change = |candidate: usize| {
+                (0..len)
+                    .map(|j| {
+                        let distance = self.distance(candidate, j).min(closest[j]);
+                        Cost::change(self.weights[j], closest[j], distance)
+                    })
+                    .sum::<Cost>()
+            };
+
+            let pick = (0..len)
+                .filter(|candidate| !medoids.contains(candidate))
+                .map(|candidate| (change(candidate), candidate))
+                .min_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)))
+                .unwrap()
+                .1;
+
+            medoids.push(pick);
+            for (j, close) in closest.iter_mut().enumerate() {
+                *close = close.min(self.distance(pick, j));
+            }
+        }
+
+        loop {
+            let (first, second) = self.closest(&medoids);
+            let swap = (0..medoids.len())
+                .into_par_iter()
+                .flat_map(|slot| {
+                    (0..len)
+                        .filter(|candidate| !medoids.contains(candidate))
+                        .map(|candidate| {
+                            let change = (0..len)
+                                .map(|j| {
+                                    let distance = self.distance(candidate, j);
+                                    let next = if first[j].1 == slot {
+                                        distance.min(second[j])
+                                    } else {
+                                        distance.min(first[j].0)
+                                    };
+
+                                    Cost::change(self.weights[j], first[j].0, next)
+                                })
+                                .sum::<Cost>();
+
+                            (change, slot, candidate)
+                        })
+                        .collect::<Vec<(Cost, usize, usize)>>()
+                })
+                .min_by(|a, b| a.0.cmp(&b.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+
+            match swap {
+                Some((change, slot, candidate)) if change.lowers() => medoids[slot] = candidate,
+                _ => break,
+            }
+        }
+
+        medoids
+            .into_iter()
+            .map(|medoid| self.points[medoid])
+            .collect()
+    }
+
+    /// The distance to and slot of the closest medoid of every point, and the
+    /// distance to the second closest.
+    fn closest(&self, medoids: &[usize]) -> (Vec<(f64, usize)>, Vec<f64>) {
+        (0..self.points.len())
+            .map(|j| {
+                let mut first = (f64::INFINITY, 0);
+                let mut second = f64::INFINITY;
+                for (slot, &medoid) in medoids.iter().enumerate() {
+                    let distance = self.distance(medoid, j);
+                    if distance < first.0 {
+                        second = first.0;
+                        first = (distance, slot);
+                    } else if distance < second {
+                        second = distance;
+                    }
+                }
+
+                (first, second)
+            })
+            .unzip()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{lines, sets};
+    use crate::cluster::{Hamming, Levenshtein, Singletons};
+
+    #[test]
+    fn medoids_represent_clusters() {
+        let lines = lines(&[
+            "kitten",
+            "sitten",
+            "kitten",
+            "mitten",
+            "sitting",
+            "banana",
+            "bandana",
+            "cabana",
+            "banana",
+            "xylophone",
+        ]);
+        let cluster = KMedoids::new(3)
+            .cluster(&Levenshtein::new(1), &lines, &lines)
+            .singletons(Singletons::Include);
+        let representatives = cluster
+            .groups()
+            .map(|(_, group)| cluster.representative(group).map(|rep| &lines[rep]))
+            .collect::<Vec<Option<&String>>>();
+
+        assert_eq!(
+            representatives,
+            vec![Some(&lines[0]), Some(&lines[5]), Some(&lines[9])]
+        );
+        assert_eq!(
+            sets(cluster),
+            vec![
+                vec![&lines[0], &lines[1], &lines[3], &lines[4]],
+                vec![&lines[5], &lines[6], &lines[7]],
+                vec![&lines[9]],
+            ]
+        );
+    }
+
+    #[test]
+    fn medoids_lead_their_members() {
+        let lines = lines(&["sitting", "kitten", "sitten", "mitten"]);
+        let cluster = KMedoids::new(1)
+            .cluster(&Levenshtein::new(1), &lines, &lines)
+            .representatives_first();
+
+        assert_eq!(
+            sets(cluster),
+            vec![vec![&lines[2], &lines[0], &lines[1], &lines[3]]]
+        );
+    }
+
+    #[test]
+    fn samples_find_the_same_medoids() {
+        let lines = lines(&[
+            "kitten", "sitten", "kitten", "mitten", "sitting", "banana", "bandana", "cabana",
+            "banana",
+        ]);
+        let algo = Levenshtein::new(1);
+
+        for sample_size in 3..8 {
+            let sampled = KMedoids::new(2)
+                .sample_size(sample_size)
+                .samples(10)
+                .cluster(&algo, &lines, &lines)
+                .singletons(Singletons::Include);
+            let exhaustive = KMedoids::new(2)
+                .cluster(&algo, &lines, &lines)
+                .singletons(Singletons::Include);
+
+            assert_eq!(sets(sampled), sets(exhaustive), "{}", sample_size);
+        }
+    }
+
+    #[test]
+    fn k_is_capped_by_distinct_keys() {
+        let lines = lines(&[
+            "kitten",
+            "sitten",
+            "kitten",
+            "banana",
+            "banana",
+            "xylophone",
+        ]);
+        let cluster = KMedoids::new(20)
+            .cluster(&Levenshtein::new(1), &lines, &lines)
+            .singletons(Singletons::Include);
+
+        assert_eq!(sets(cluster).len(), 4);
+    }
+
+    #[test]
+    fn infinite_distances() {
+        let lines = lines(&["ab", "abc", "xyz", "abd", "ac"]);
+        let cluster = KMedoids::new(2).cluster(&Hamming::new(2), &lines, &lines);
+
+        assert_eq!(
+            sets(cluster),
+            vec![
+                vec![&lines[0], &lines[4]],
+                vec![&lines[1], &lines[2], &lines[3]]
+            ]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use super::ClusterAlgo;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// Finds candidate pairs by locality-sensitive hashing of MinHash signatures
+/// instead of comparing every pair.
+///
+/// Each record is split into shingles of consecutive words and summarized by
+/// `bands * rows` minimum hashes. Records whose signatures agree on every row
+/// of some band become candidates, which are then verified by the wrapped
+/// algorithm. Pairs with shingle similarity `s` are proposed with probability
+/// `1 - (1 - s^rows)^bands`, so some accepted pairs may be missed; this suits
+/// long records such as paragraphs, where comparing every pair is infeasible.
+pub struct MinHash<CA> {
+    algo: CA,
+    bands: usize,
+    rows: usize,
+    shingle: usize,
+    found: Mutex<Option<f64>>,
+}
+
+impl<CA> MinHash<CA>
+where
+    CA: ClusterAlgo,
+{
+    /// Verifies candidates with `algo`, using 16 bands of 4 rows over shingles of 3 words.
+    pub fn new(algo: CA) -> MinHash<CA> {
+        MinHash {
+            algo,
+            bands: 16,
+            rows: 4,
+            shingle: 3,
+            found: Mutex::new(None),
+        }
+    }
+
+    /// Split signatures into `bands`, at least one.
+    ///
+    /// More bands find more pairs at the cost of more candidates.
+    pub fn bands(mut self, bands: usize) -> MinHash<CA> {
+        self.bands = bands.max(1);
+        self
+    }
+
+    /// Hashes per band, at least one.
+    ///
+    /// More rows propose fewer dissimilar pairs at the cost of missing more similar ones.
+    pub fn rows(mut self, rows: usize) -> MinHash<CA> {
+        self.rows = rows.max(1);
+        self
+    }
+
+    /// Words per shingle, at least one.
+    ///
+    /// Records with fewer words are a single shingle.
+    pub fn shingle(mut self, shingle: usize) -> MinHash<CA> {
+        self.shingle = shingle.max(1);
+        self
+    }
+
+    /// Probability that a pair with shingle jaccard `similarity` is proposed.
+    pub fn recall(&self, similarity: f64) -> f64 {
+        1.0 - (1.0 - similarity.powi(self.rows as i32)).powi(self.bands as i32)
+    }
+
+    /// Estimated share of accepted pairs found by the last call to [`ClusterAlgo::pairs`].
+    ///
+    /// Each found pair stands in for the pairs of its similarity that were
+    /// missed, weighting it by the inverse of its [`MinHash::recall`]. `None`
+    /// until pairs have been found.
+    pub fn estimated_recall(&self) -> Option<f64> {
+        *self.found.lock().unwrap()
+    }
+
+    fn shingles(&self, record: &str) -> Vec<u64> {
+        let words = record.split_whitespace().collect::<Vec<&str>>();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let mut shingles = words
+            .windows(self.shingle.min(words.len()))
+            .map(|window| stable_hash(window.iter().cloned()))
+            .collect::<Vec<u64>>();
+
+        shingles.sort_unstable();
+        shingles.dedup();
+        shingles
+    }
+
+    fn signature(&self, shingles: &[u64], seeds: &[u64]) -> Vec<u64> {
+        seeds
+            .iter()
+            .map(|&seed| {
+                shingles
+                    .iter()
+                    .map(|&shingle| mix(shingle ^ seed))
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect()
+    }
+}
+
+impl<CA> ClusterAlgo for MinHash<CA>
+where
+    CA: ClusterAlgo,
+{
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.algo.accept(first, second)
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        self.algo.distance(first, second)
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        self.algo.cutoff()
+    }
+
+    fn observe(&mut self, record: &str) {
+        self.algo.observe(record)
+    }
+
+    fn pairs(&self, lines: &[String]) -> Vec<(usize, usize)> {
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+        let seeds = (0..self.bands * self.rows)
+            .map(|_| {
+                state = mix(state);
+                state
+            })
+            .collect::<Vec<u64>>();
+
+        let shingles = lines
+            .par_iter()
+            .map(|line| self.shingles(line))
+            .collect::<Vec<Vec<u64>>>();
+
+        let signatures = shingles
+            .par_iter()
+            .map(|shingles| self.signature(shingles, &seeds))
+            .collect::<Vec<Vec<u64>>>();
+
+        let mut buckets = HashMap::new();
+        for (index, signature) in signatures.iter().enumerate() {
+            for (band, rows) in signature.chunks(self.rows).enumerate() {
+                buckets
+                    .entry((band, rows))
+                    .or_insert_with(Vec::new)
+                    .push(index);
+            }
+        }
+
+        let candidates = buckets
+            .values()
+            .flat_map(|bucket| {
+                bucket.iter().enumerate().flat_map(move |(n, &first)| {
+                    bucket[n + 1..].iter().map(move |&second| (first, second))
+                })
+            })
+            .collect::<HashSet<(usize, usize)>>();
+
+        let pairs = candidates
+            .into_par_iter()
+            .filter(|&(first, second)| self.accept(&lines[first], &lines[second]))
+            .collect::<Vec<(usize, usize)>>();
+
+        let weight = pairs
+            .iter()
+            .map(|&(first, second)| {
+                let similarity = jaccard(&shingles[first], &shingles[second]);
+                1.0 / self.recall(similarity).max(f64::EPSILON)
+            })
+            .sum::<f64>();
+
+        *self.found.lock().unwrap() = if pairs.is_empty() {
+            None
+        } else {
+            Some(pairs.len() as f64 / weight)
+        };
+
+        pairs
+    }
+}
+
+/// Jaccard similarity of two sorted, deduplicated slices.
+fn jaccard(first: &[u64], second: &[u64]) -> f64 {
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < first.len() && j < second.len() {
+        match first[i].cmp(&second[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    let union = first.len() + second.len() - shared;
+    if union == 0 {
+        1.0
+    } else {
+        shared as f64 / union as f64
+    }
+}
+
+/// A 64 bit hash of `parts` that stays the same across builds: FNV-1a over
+/// their bytes, each part followed by a `0xff` byte that never occurs in
+/// UTF-8, finished with [`mix`] to spread short inputs over every bit.
+pub(super) fn stable_hash<'a, I>(parts: I) -> u64
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for part in parts {
+        for &byte in part.as_bytes().iter().chain(&[0xff]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    mix(hash)
+}
+
+/// The splitmix64 finalizer, used as a family of hash functions by xoring in a seed.
+pub(super) fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{paragraphs, sorted};
+    use crate::cluster::NormLevenshtein;
+
+    #[test]
+    fn hashes_are_pinned() {
+        assert_eq!(stable_hash(Vec::new()), 0xc381_7c01_6ba4_ff30);
+        assert_eq!(stable_hash(vec!["quick", "brown"]), 0x6e28_6800_1419_e81a);
+        assert_eq!(stable_hash(vec!["quickbrown"]), 0x88b2_7ce7_1800_99a6);
+    }
+
+    #[test]
+    fn finds_near_duplicates() {
+        let lines = paragraphs();
+        let algo = MinHash::new(NormLevenshtein::new(0.9));
+
+        assert_eq!(sorted(algo.pairs(&lines)), vec![(0, 1), (0, 3), (1, 3)]);
+
+        let recall = algo.estimated_recall().unwrap();
+        assert!(0.0 < recall && recall <= 1.0);
+    }
+
+    #[test]
+    fn recall_rises_with_similarity() {
+        let algo = MinHash::new(NormLevenshtein::new(0.9)).bands(20).rows(5);
+
+        assert_eq!(algo.recall(0.0), 0.0);
+        assert_eq!(algo.recall(1.0), 1.0);
+        assert!(algo.recall(0.3) < 0.05);
+        assert!(algo.recall(0.9) > 0.99);
+    }
+}
@@ -1,94 +1,697 @@
+//! Clustering algorithms and the clusters they produce.
+
 use crate::combinations::*;
-use crate::utils::run_cluster;
-use clap::ArgMatches;
+use crate::mask;
+use disjoint_set::DisjointSet;
 use rayon::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
+mod bk_tree;
+mod cosine;
+mod dbscan;
+mod dendrogram;
+mod dice;
+mod disjoint_set;
+mod hamming;
+mod jaccard;
 mod jaro;
+mod leader;
 mod levenshtein;
+mod linkage;
+mod medoids;
+mod min_hash;
 mod norm_levenshtein;
 mod osa;
+mod phonetic;
+mod sim_hash;
+#[cfg(test)]
+mod test_util;
+mod token;
 
+pub use bk_tree::{BkTree, Metric};
+pub use cosine::{Cosine, Tokenizer, ENGLISH_STOPWORDS};
+pub use dbscan::Point;
+pub use dendrogram::{Dendrogram, Merge};
+pub use dice::Dice;
+pub use hamming::{Hamming, Unequal};
+pub use jaccard::Jaccard;
 pub use jaro::Jaro;
+pub use leader::Leader;
 pub use levenshtein::Levenshtein;
+pub use linkage::Linkage;
+pub use medoids::KMedoids;
+pub use min_hash::MinHash;
 pub use norm_levenshtein::NormLevenshtein;
 pub use osa::OSA;
+pub use phonetic::Phonetic;
+pub use sim_hash::SimHash;
+pub use token::{TokenSet, TokenSort};
+
+/// Which groups a [`Cluster`] yields when iterated.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Singletons {
+    /// Only groups of two or more records.
+    Exclude,
+    /// Every group, including records that matched nothing.
+    Include,
+    /// Only records that matched nothing.
+    Only,
+}
+
+impl Singletons {
+    fn keep(self, len: usize) -> bool {
+        match self {
+            Singletons::Exclude => len > 1,
+            Singletons::Include => true,
+            Singletons::Only => len == 1,
+        }
+    }
+}
+
+/// How the groups of a [`Cluster`] and their members are ordered.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Order {
+    /// Groups by their first record, members in input order.
+    First,
+    /// Largest groups first, ties broken by their first record.
+    Size,
+    /// Members sorted lexicographically and groups by their smallest member.
+    Lex,
+}
 
+/// How a single exemplar is chosen for each group.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Representative {
+    /// The member with the least total distance to the others.
+    Medoid,
+    /// The most common exact value.
+    Frequent,
+    /// The member with the fewest characters.
+    Shortest,
+    /// The member seen first in the input.
+    First,
+}
+
+/// Groups of records that were found to be similar to each other.
 pub struct Cluster<'a> {
-    clusters: Vec<HashSet<&'a String>>,
+    records: Vec<&'a String>,
+    groups: Vec<Vec<usize>>,
+    singletons: Singletons,
+    representatives: HashSet<usize>,
+    templates: HashMap<usize, String>,
+    points: Vec<Point>,
 }
 
 impl<'a> Cluster<'a> {
+    /// Builds clusters from accepted pairs, joining any pairs that share a record.
     pub fn pairwise(pairs: Vec<(&'a String, &'a String)>) -> Cluster<'a> {
-        let mut clusters: Vec<HashSet<&'a String>> = Vec::new();
-        'pairwise: for (first, second) in pairs {
-            for cluster in &mut clusters {
-                if cluster.contains(&first) || cluster.contains(&second) {
-                    cluster.insert(&first);
-                    cluster.insert(&second);
-                    continue 'pairwise;
-                }
-            }
+        let mut records = Vec::new();
+        let mut indices = HashMap::new();
+        let mut index_of = |record: &'a String| {
+            *indices.entry(record).or_insert_with(|| {
+                records.push(record);
+                records.len() - 1
+            })
+        };
+
+        let pairs = pairs
+            .into_iter()
+            .map(|(first, second)| (index_of(first), index_of(second)))
+            .collect::<Vec<(usize, usize)>>();
+
+        Cluster::connect(records, pairs)
+    }
+
+    /// Builds clusters from accepted pairs of indices into `records`.
+    pub fn from_pairs(records: &'a [String], pairs: Vec<(usize, usize)>) -> Cluster<'a> {
+        Cluster::connect(records.iter().collect(), pairs)
+    }
 
-            let mut new_set = HashSet::new();
-            new_set.insert(first);
-            new_set.insert(second);
+    fn connect(records: Vec<&'a String>, pairs: Vec<(usize, usize)>) -> Cluster<'a> {
+        let mut set = DisjointSet::new(records.len());
+        for (first, second) in pairs {
+            set.union(first, second);
+        }
+
+        let groups = set.sets();
+        let singletons = Singletons::Exclude;
+        let representatives = HashSet::new();
+        let templates = HashMap::new();
+        let points = Vec::new();
 
-            clusters.push(new_set);
+        Cluster {
+            records,
+            groups,
+            singletons,
+            representatives,
+            templates,
+            points,
         }
+    }
 
-        clusters = Cluster::merge(clusters);
+    /// Records the density role of every record, dropping groups made up of
+    /// [`Point::Noise`] records so they are only reported by [`Cluster::noise`].
+    pub fn points(mut self, points: Vec<Point>) -> Cluster<'a> {
+        self.groups
+            .retain(|group| group.iter().any(|&index| points[index] != Point::Noise));
+        self.points = points;
+        self
+    }
 
-        Cluster { clusters }
+    /// The density role of the record at `index`, once recorded with
+    /// [`Cluster::points`].
+    pub fn point(&self, index: usize) -> Option<Point> {
+        self.points.get(index).cloned()
     }
 
-    fn merge(clusters: Vec<HashSet<&'a String>>) -> Vec<HashSet<&'a String>> {
-        let mut result = Vec::new();
+    /// Indices of the records that are [`Point::Noise`], in record order.
+    pub fn noise(&self) -> Vec<usize> {
+        self.points
+            .iter()
+            .enumerate()
+            .filter(|&(_, &point)| point == Point::Noise)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Selects whether unmatched records are yielded as clusters of their own.
+    ///
+    /// Clusters built with [`Cluster::pairwise`] only know about paired records
+    /// and so never contain singletons.
+    pub fn singletons(mut self, singletons: Singletons) -> Cluster<'a> {
+        self.singletons = singletons;
+        self
+    }
+
+    /// Reorders groups and their members, renumbering group ids to match.
+    ///
+    /// Groups start out in [`Order::First`] order.
+    pub fn sort(mut self, order: Order) -> Cluster<'a> {
+        let records = &self.records;
+
+        match order {
+            Order::First => {
+                for group in &mut self.groups {
+                    group.sort_unstable();
+                }
+
+                self.groups.sort_by_key(|group| group[0]);
+            }
 
-        for mut cluster in clusters {
-            let mut c_index = Vec::new();
-            for (i, c) in result.iter().enumerate() {
-                if !cluster.is_disjoint(c) {
-                    c_index.push(i)
+            Order::Size => {
+                for group in &mut self.groups {
+                    group.sort_unstable();
                 }
+
+                self.groups
+                    .sort_by(|a, b| b.len().cmp(&a.len()).then(a[0].cmp(&b[0])));
             }
 
-            for i in c_index.iter().rev() {
-                for elem in result.remove(*i) {
-                    cluster.insert(elem);
+            Order::Lex => {
+                for group in &mut self.groups {
+                    group.sort_by(|&a, &b| records[a].cmp(records[b]).then(a.cmp(&b)));
+                }
+
+                self.groups
+                    .sort_by(|a, b| records[a[0]].cmp(records[b[0]]).then(a[0].cmp(&b[0])));
+            }
+        }
+
+        self
+    }
+
+    /// Chooses a representative for every group, ties going to the earliest record.
+    ///
+    /// Medoids are measured with [`ClusterAlgo::distance`] of `algo`.
+    pub fn representatives<A>(mut self, pick: Representative, algo: &A) -> Cluster<'a>
+    where
+        A: ClusterAlgo,
+    {
+        let records = &self.records;
+
+        self.representatives = self
+            .groups
+            .par_iter()
+            .map(|group| {
+                let first = *group.iter().min().unwrap();
+
+                match pick {
+                    Representative::First => first,
+
+                    Representative::Shortest => *group
+                        .iter()
+                        .min_by_key(|&&i| (records[i].chars().count(), i))
+                        .unwrap(),
+
+                    Representative::Frequent => {
+                        let mut counts = HashMap::new();
+                        for &i in group {
+                            let entry = counts.entry(records[i]).or_insert((0, i));
+                            entry.0 += 1;
+                            entry.1 = entry.1.min(i);
+                        }
+
+                        counts
+                            .values()
+                            .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+                            .unwrap()
+                            .1
+                    }
+
+                    Representative::Medoid => {
+                        let members = self.members(group);
+                        members
+                            .iter()
+                            .map(|(record, indices)| {
+                                let total = members
+                                    .iter()
+                                    .map(|(other, copies)| {
+                                        copies.len() as f64 * algo.distance(record, other)
+                                    })
+                                    .sum::<f64>();
+
+                                (total, indices[0])
+                            })
+                            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.cmp(&b.1)))
+                            .unwrap()
+                            .1
+                    }
                 }
+            })
+            .collect();
+
+        self
+    }
+
+    /// Moves each group's representative, if it has one, to the front of its
+    /// members, keeping the order of the others and of the groups.
+    pub fn representatives_first(mut self) -> Cluster<'a> {
+        let representatives = &self.representatives;
+        for group in &mut self.groups {
+            let position = group
+                .iter()
+                .position(|index| representatives.contains(index));
+
+            if let Some(position) = position {
+                group[..=position].rotate_right(1);
             }
+        }
+
+        self
+    }
+
+    /// The representative of `group`, once chosen with [`Cluster::representatives`].
+    pub fn representative(&self, group: &[usize]) -> Option<usize> {
+        group
+            .iter()
+            .find(|i| self.representatives.contains(i))
+            .cloned()
+    }
 
-            result.push(cluster);
+    /// Derives a template for every group from `keys`, the compared form of
+    /// each record, replacing tokens that vary within the group by wildcards.
+    pub fn templates(mut self, keys: &[String]) -> Cluster<'a> {
+        self.templates = self
+            .groups
+            .par_iter()
+            .map(|group| {
+                let first = *group.iter().min().unwrap();
+                let template = mask::template(group.iter().map(|&i| keys[i].as_str()));
+
+                (first, template)
+            })
+            .collect();
+
+        self
+    }
+
+    /// The template of `group`, once derived with [`Cluster::templates`].
+    pub fn template(&self, group: &[usize]) -> Option<&str> {
+        group
+            .iter()
+            .min()
+            .and_then(|first| self.templates.get(first))
+            .map(|template| template.as_str())
+    }
+
+    /// The distinct values of `group` in order, each with the indices of its records.
+    pub fn members(&self, group: &[usize]) -> Vec<(&'a String, Vec<usize>)> {
+        let mut slots = HashMap::new();
+        let mut members: Vec<(&'a String, Vec<usize>)> = Vec::new();
+
+        for &index in group {
+            let record = self.records[index];
+            let slot = *slots.entry(record).or_insert_with(|| {
+                members.push((record, Vec::new()));
+                members.len() - 1
+            });
+
+            members[slot].1.push(index);
+        }
+
+        members
+    }
+
+    /// Each selected group with its id, as indices of its records.
+    ///
+    /// Ids are stable regardless of which singletons are selected. For clusters
+    /// built with [`Cluster::from_pairs`] indices match positions in the input.
+    pub fn groups(&self) -> impl Iterator<Item = (usize, &[usize])> + '_ {
+        self.groups
+            .iter()
+            .enumerate()
+            .filter(move |(_, group)| self.singletons.keep(group.len()))
+            .map(|(id, group)| (id, group.as_slice()))
+    }
+
+    /// The id of the selected group holding each record, in record order.
+    pub fn labels(&self) -> Vec<Option<usize>> {
+        let mut labels = vec![None; self.records.len()];
+        for (id, group) in self.groups() {
+            for &index in group {
+                labels[index] = Some(id);
+            }
         }
 
-        result
+        labels
+    }
+
+    /// The record at `index`.
+    pub fn record(&self, index: usize) -> &'a String {
+        self.records[index]
     }
 }
 
+/// Yields the distinct values of each selected group in order.
 impl<'a> IntoIterator for Cluster<'a> {
-    type Item = HashSet<&'a String>;
+    type Item = Vec<&'a String>;
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.clusters.into_iter()
+        self.groups()
+            .map(|(_, group)| {
+                self.members(group)
+                    .into_iter()
+                    .map(|(record, _)| record)
+                    .collect()
+            })
+            .collect::<Vec<Self::Item>>()
+            .into_iter()
     }
 }
 
+/// A similarity test used to decide which records belong together.
 pub trait ClusterAlgo: Sized + Sync {
-    fn from_matches(matches: &ArgMatches) -> Self;
-    fn accept<'a>(&self, first: &'a String, second: &'a String) -> bool;
+    /// Whether `first` and `second` are similar enough to share a cluster.
+    fn accept(&self, first: &str, second: &str) -> bool;
 
-    fn run(matches: &ArgMatches) {
-        run_cluster(&matches, Self::from_matches(&matches))
+    /// How far apart `first` and `second` are, lower being more similar.
+    ///
+    /// Defaults to `0.0` for accepted pairs and `1.0` otherwise.
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        if self.accept(first, second) {
+            0.0
+        } else {
+            1.0
+        }
     }
 
-    fn cluster<'a>(&self, lines: &'a Vec<String>) -> Cluster<'a> {
-        let pairs = combinations(lines)
+    /// The distance below which pairs are accepted, if acceptance is a cut on
+    /// [`ClusterAlgo::distance`].
+    fn cutoff(&self) -> Option<f64> {
+        None
+    }
+
+    /// Takes `record` into account before it is compared, when records arrive
+    /// one at a time as in [`Leader`].
+    ///
+    /// Does nothing by default.
+    fn observe(&mut self, _record: &str) {}
+
+    /// Indices of every pair of `lines` that is accepted.
+    fn pairs(&self, lines: &[String]) -> Vec<(usize, usize)> {
+        let indices = (0..lines.len()).collect::<Vec<usize>>();
+
+        combinations(&indices)
             .par_bridge()
-            .filter(|(a, b)| self.accept(a, b))
-            .collect::<Vec<(&String, &String)>>();
+            .map(|(&a, &b)| (a, b))
+            .filter(|&(a, b)| self.accept(&lines[a], &lines[b]))
+            .collect()
+    }
+
+    /// Compares every pair of distinct `lines` and clusters the accepted ones.
+    fn cluster<'a>(&self, lines: &'a [String]) -> Cluster<'a> {
+        self.cluster_by(lines, lines)
+    }
+
+    /// Clusters `lines` by comparing `keys`, a preprocessed form of each line,
+    /// such as the output of [`Masker::masks`](crate::mask::Masker::masks).
+    ///
+    /// Only distinct keys are compared and lines with equal keys always share
+    /// a cluster.
+    fn cluster_by<'a>(&self, lines: &'a [String], keys: &[String]) -> Cluster<'a> {
+        self.cluster_linked(lines, keys, Linkage::Single)
+    }
+
+    /// Clusters `lines` by comparing `keys` like [`ClusterAlgo::cluster_by`],
+    /// merging groups of distinct keys according to `linkage`.
+    fn cluster_linked<'a>(
+        &self,
+        lines: &'a [String],
+        keys: &[String],
+        linkage: Linkage,
+    ) -> Cluster<'a> {
+        let (values, firsts, slots) = distinct(keys);
+        let pairs = linkage::merge(self, &values, self.pairs(&values), linkage);
+
+        Cluster::from_pairs(lines, expand(&firsts, &slots, pairs))
+    }
+
+    /// Clusters `lines` by the density of accepted `keys`, as in DBSCAN.
+    ///
+    /// Keys with at least `min_points` lines accepted by them, counting their
+    /// own, are cores and chain together, while other keys join their closest
+    /// core or are left out as noise.
+    fn cluster_dense<'a>(
+        &self,
+        lines: &'a [String],
+        keys: &[String],
+        min_points: usize,
+    ) -> Cluster<'a> {
+        let (values, firsts, slots) = distinct(keys);
+        let mut counts = vec![0; values.len()];
+        for &slot in &slots {
+            counts[slot] += 1;
+        }
+
+        let (pairs, points) =
+            dbscan::classify(self, &values, &counts, self.pairs(&values), min_points);
+        let points = slots.iter().map(|&slot| points[slot]).collect();
+
+        Cluster::from_pairs(lines, expand(&firsts, &slots, pairs)).points(points)
+    }
+}
+
+/// `pairs` of distinct keys as pairs of lines, joining lines with equal keys.
+fn expand(firsts: &[usize], slots: &[usize], pairs: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut pairs = pairs
+        .into_iter()
+        .map(|(a, b)| (firsts[a], firsts[b]))
+        .collect::<Vec<(usize, usize)>>();
+
+    for (index, &slot) in slots.iter().enumerate() {
+        if firsts[slot] != index {
+            pairs.push((firsts[slot], index));
+        }
+    }
+
+    pairs
+}
+
+/// The distinct `keys` in first seen order, the index of the first key equal
+/// to each of them, and the position among them of every key.
+fn distinct(keys: &[String]) -> (Vec<String>, Vec<usize>, Vec<usize>) {
+    let mut positions = HashMap::new();
+    let mut values = Vec::new();
+    let mut firsts = Vec::new();
+
+    let slots = keys
+        .iter()
+        .enumerate()
+        .map(|(index, key)| {
+            *positions.entry(key).or_insert_with(|| {
+                values.push(key.clone());
+                firsts.push(index);
+                values.len() - 1
+            })
+        })
+        .collect();
+
+    (values, firsts, slots)
+}
+
+#[cfg(test)]
+mod test {
+    use super::test_util::{lines, sets};
+    use super::*;
+
+    #[test]
+    fn chained_pairs_form_one_cluster() {
+        let lines = lines(&["a", "b", "c", "d", "e"]);
+
+        let cluster = Cluster::from_pairs(&lines, vec![(0, 1), (3, 2), (1, 3)]);
+
+        assert_eq!(
+            sets(cluster),
+            vec![vec![&lines[0], &lines[1], &lines[2], &lines[3]]]
+        );
+    }
+
+    #[test]
+    fn singleton_modes() {
+        let lines = lines(&["a", "b", "c"]);
+
+        let sizes = |singletons| {
+            Cluster::from_pairs(&lines, vec![(0, 2)])
+                .singletons(singletons)
+                .into_iter()
+                .map(|set| set.len())
+                .collect::<Vec<usize>>()
+        };
+
+        assert_eq!(sizes(Singletons::Exclude), vec![2]);
+        assert_eq!(sizes(Singletons::Include), vec![2, 1]);
+        assert_eq!(sizes(Singletons::Only), vec![1]);
+    }
+
+    #[test]
+    fn labels_follow_record_order() {
+        let lines = lines(&["a", "b", "c", "d"]);
+
+        let cluster = Cluster::from_pairs(&lines, vec![(1, 3)]);
+        assert_eq!(cluster.labels(), vec![None, Some(1), None, Some(1)]);
+
+        let cluster = cluster.singletons(Singletons::Include);
+        assert_eq!(cluster.labels(), vec![Some(0), Some(1), Some(2), Some(1)]);
+    }
+
+    #[test]
+    fn sort_orders() {
+        let lines = lines(&["d", "b", "c", "a", "e"]);
+
+        let sorted = |order| {
+            Cluster::from_pairs(&lines, vec![(2, 3), (4, 3)])
+                .singletons(Singletons::Include)
+                .sort(order)
+                .into_iter()
+                .map(|group| group.iter().map(|s| s.as_str()).collect::<String>())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(sorted(Order::First), vec!["d", "b", "cae"]);
+        assert_eq!(sorted(Order::Size), vec!["cae", "d", "b"]);
+        assert_eq!(sorted(Order::Lex), vec!["ace", "b", "d"]);
+    }
+
+    #[test]
+    fn representative_picks() {
+        let lines = lines(&["hallo", "hello", "help", "hello", "hell", "x"]);
+
+        let algo = Levenshtein::new(3);
+        let rep = |pick| {
+            let cluster = algo.cluster(&lines).representatives(pick, &algo);
+            let (_, group) = cluster.groups().next().unwrap();
+            cluster.representative(group).unwrap()
+        };
+
+        assert_eq!(rep(Representative::First), 0);
+        assert_eq!(rep(Representative::Shortest), 2);
+        assert_eq!(rep(Representative::Frequent), 1);
+        assert_eq!(rep(Representative::Medoid), 1);
+    }
+
+    #[test]
+    fn templates_from_masked_keys() {
+        let lines = lines(&[
+            "closed 10.0.0.1 after 20ms",
+            "closed 10.0.0.7 after 4ms",
+            "opened 10.0.0.7",
+        ]);
+
+        let keys = mask::Masker::all().masks(&lines);
+        let cluster = Levenshtein::new(1)
+            .cluster_by(&lines, &keys)
+            .templates(&keys);
+
+        let templates = cluster
+            .groups()
+            .map(|(_, group)| cluster.template(group).unwrap())
+            .collect::<Vec<&str>>();
+
+        assert_eq!(templates, vec!["closed <IP> after <NUM>ms"]);
+    }
+
+    #[test]
+    fn templates_from_normalized_keys() {
+        let lines = lines(&[
+            "Closed 10.0.0.1,  after 20ms",
+            "closed 10.0.0.7 after 4ms",
+            "Foo  Bar",
+            "foo bar",
+        ]);
+
+        let masker = mask::Masker::all();
+        let keys = crate::normalize::Normalizer::all()
+            .keep(&masker.placeholders())
+            .normalize_all(&masker.masks(&lines));
+        let cluster = Levenshtein::new(1)
+            .cluster_by(&lines, &keys)
+            .templates(&keys);
+
+        let templates = cluster
+            .groups()
+            .map(|(_, group)| cluster.template(group).unwrap())
+            .collect::<Vec<&str>>();
+
+        assert_eq!(templates, vec!["closed <IP> after <NUM>ms", "foo bar"]);
+    }
+
+    #[test]
+    fn duplicates_compared_once() {
+        struct Counting(std::sync::atomic::AtomicUsize);
+
+        impl ClusterAlgo for Counting {
+            fn accept(&self, first: &str, second: &str) -> bool {
+                self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                first.len() == second.len()
+            }
+        }
+
+        let lines = lines(&["ab", "c", "ab", "ab", "cd", "c"]);
+
+        let algo = Counting(Default::default());
+        let cluster = algo.cluster(&lines).singletons(Singletons::Include);
+
+        assert_eq!(algo.0.into_inner(), 3);
+
+        let groups = cluster
+            .groups()
+            .map(|(_, g)| g.to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(groups, vec![vec![0, 2, 3, 4], vec![1, 5]]);
+
+        let members = cluster.members(&groups[0]);
+        assert_eq!(members[0], (&lines[0], vec![0, 2, 3]));
+        assert_eq!(members[1], (&lines[4], vec![4]));
+    }
+
+    #[test]
+    fn pairwise_joins_equal_records() {
+        let (a, b, c) = ("a".to_string(), "b".to_string(), "c".to_string());
+        let other_a = a.clone();
+
+        let cluster = Cluster::pairwise(vec![(&a, &b), (&c, &other_a)]);
 
-        Cluster::pairwise(pairs)
+        assert_eq!(cluster.into_iter().count(), 1);
     }
 }
@@ -1,27 +1,46 @@
 use super::ClusterAlgo;
-use clap::ArgMatches;
 use strsim::{normalized_damerau_levenshtein, normalized_levenshtein};
 
+/// Clusters records whose normalized levenshtein similarity exceeds a ratio.
 pub struct NormLevenshtein {
     ratio: f64,
     damerau: bool,
 }
 
-impl ClusterAlgo for NormLevenshtein {
-    fn from_matches(matches: &ArgMatches) -> Self {
-        let ratio = matches.value_of("ratio").unwrap().parse::<f64>().unwrap();
-        let damerau = matches.is_present("damerau");
+impl NormLevenshtein {
+    /// Accepts pairs with a normalized similarity above `ratio`.
+    pub fn new(ratio: f64) -> NormLevenshtein {
+        NormLevenshtein {
+            ratio,
+            damerau: false,
+        }
+    }
 
-        NormLevenshtein { ratio, damerau }
+    /// Count adjacent transpositions as a single edit.
+    pub fn damerau(mut self, damerau: bool) -> NormLevenshtein {
+        self.damerau = damerau;
+        self
     }
 
-    fn accept<'a>(&self, first: &'a String, second: &'a String) -> bool {
-        let sim = if self.damerau {
+    fn similarity(&self, first: &str, second: &str) -> f64 {
+        if self.damerau {
             normalized_damerau_levenshtein(first, second)
         } else {
             normalized_levenshtein(first, second)
-        };
+        }
+    }
+}
+
+impl ClusterAlgo for NormLevenshtein {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.similarity(first, second) > self.ratio
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        1.0 - self.similarity(first, second)
+    }
 
-        sim > self.ratio
+    fn cutoff(&self) -> Option<f64> {
+        Some(1.0 - self.ratio)
     }
 }
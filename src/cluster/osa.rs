@@ -1,23 +1,42 @@
-use super::ClusterAlgo;
-use clap::ArgMatches;
-use strsim::osa_distance;
+use super::{ClusterAlgo, Metric};
+use strsim::{levenshtein, osa_distance};
 
+/// Clusters records within an optimal string alignment distance.
+#[allow(clippy::upper_case_acronyms)]
 pub struct OSA {
     threshold: usize,
 }
-impl ClusterAlgo for OSA {
-    fn from_matches(matches: &ArgMatches) -> Self {
-        let threshold = matches
-            .value_of("threshold")
-            .unwrap()
-            .parse::<usize>()
-            .unwrap();
 
+impl OSA {
+    /// Accepts pairs with an alignment distance below `threshold`.
+    pub fn new(threshold: usize) -> OSA {
         OSA { threshold }
     }
+}
 
-    fn accept<'a>(&self, first: &'a String, second: &'a String) -> bool {
+impl ClusterAlgo for OSA {
+    fn accept(&self, first: &str, second: &str) -> bool {
         let sim = osa_distance(first, second);
         sim < self.threshold
     }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        osa_distance(first, second) as f64
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.threshold as f64)
+    }
+}
+
+/// Alignment distance breaks the triangle inequality, so candidates are bounded
+/// by levenshtein distance, which is at most two edits per transposition.
+impl Metric for OSA {
+    fn metric(&self, first: &str, second: &str) -> usize {
+        levenshtein(first, second)
+    }
+
+    fn radius(&self) -> Option<usize> {
+        self.threshold.checked_sub(1).map(|max| max * 2)
+    }
 }
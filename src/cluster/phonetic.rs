@@ -0,0 +1,178 @@
+use super::ClusterAlgo;
+use crate::combinations::*;
+use crate::phonetic::Encoding;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use strsim::levenshtein;
+
+/// Clusters records that sound alike under a phonetic [`Encoding`].
+///
+/// By default records are blocked on their codes, pairing records that share
+/// any code exactly. With a threshold, codes within an edit distance match too.
+pub struct Phonetic {
+    encoding: Encoding,
+    threshold: Option<usize>,
+}
+
+impl Phonetic {
+    /// Accepts pairs sharing a code under `encoding`.
+    pub fn new(encoding: Encoding) -> Phonetic {
+        Phonetic {
+            encoding,
+            threshold: None,
+        }
+    }
+
+    /// Accepts pairs whose closest codes have fewer than `threshold` edits instead.
+    pub fn threshold(mut self, threshold: usize) -> Phonetic {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Edits between the closest codes, `None` if either side has no codes.
+    fn edits(first: &[String], second: &[String]) -> Option<usize> {
+        first
+            .iter()
+            .flat_map(|a| second.iter().map(move |b| levenshtein(a, b)))
+            .min()
+    }
+
+    fn matches(&self, first: &[String], second: &[String]) -> bool {
+        match self.threshold {
+            Some(threshold) => {
+                Phonetic::edits(first, second).is_some_and(|edits| edits < threshold)
+            }
+            None => first.iter().any(|code| second.contains(code)),
+        }
+    }
+}
+
+impl ClusterAlgo for Phonetic {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.matches(&self.encoding.codes(first), &self.encoding.codes(second))
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        Phonetic::edits(&self.encoding.codes(first), &self.encoding.codes(second))
+            .map_or(f64::INFINITY, |edits| edits as f64)
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.threshold.map_or(1.0, |threshold| threshold as f64))
+    }
+
+    fn pairs(&self, lines: &[String]) -> Vec<(usize, usize)> {
+        let codes = lines
+            .par_iter()
+            .map(|line| self.encoding.codes(line))
+            .collect::<Vec<Vec<String>>>();
+
+        if self.threshold.is_some() {
+            let indices = (0..lines.len()).collect::<Vec<usize>>();
+
+            return combinations(&indices)
+                .par_bridge()
+                .filter(|&(&first, &second)| self.matches(&codes[first], &codes[second]))
+                .map(|(&first, &second)| (first, second))
+                .collect();
+        }
+
+        let mut blocks = HashMap::new();
+        for (index, codes) in codes.iter().enumerate() {
+            for code in codes {
+                blocks.entry(code).or_insert_with(Vec::new).push(index);
+            }
+        }
+
+        blocks
+            .values()
+            .flat_map(|block| {
+                block.iter().enumerate().flat_map(move |(n, &first)| {
+                    block[n + 1..].iter().map(move |&second| (first, second))
+                })
+            })
+            .collect::<HashSet<(usize, usize)>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{brute_force, lines, sorted};
+
+    fn names() -> Vec<String> {
+        lines(&[
+            "Smith",
+            "Schmidt",
+            "Smyth",
+            "Catherine",
+            "Katherine",
+            "Kathryn",
+            "Jones",
+        ])
+    }
+
+    #[test]
+    fn blocks_on_codes() {
+        let lines = names();
+
+        assert_eq!(
+            sorted(Phonetic::new(Encoding::Soundex).pairs(&lines)),
+            vec![(0, 1), (0, 2), (1, 2), (4, 5)]
+        );
+        assert_eq!(
+            sorted(Phonetic::new(Encoding::Metaphone).pairs(&lines)),
+            vec![(0, 1), (0, 2), (1, 2), (3, 4), (3, 5), (4, 5)]
+        );
+    }
+
+    #[test]
+    fn words_match_by_either_code() {
+        let lines = lines(&["John Smith", "John Schmidt", "Jon Smyth"]);
+
+        assert_eq!(
+            sorted(Phonetic::new(Encoding::Metaphone).pairs(&lines)),
+            vec![(0, 1), (0, 2), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn records_without_letters_match_nothing() {
+        let lines = lines(&["12345", "99999", "---", "Smith", "SMITH"]);
+
+        for encoding in [Encoding::Soundex, Encoding::Metaphone, Encoding::Nysiis]
+            .iter()
+            .cloned()
+        {
+            for algo in &[
+                Phonetic::new(encoding),
+                Phonetic::new(encoding).threshold(2),
+            ] {
+                assert_eq!(sorted(algo.pairs(&lines)), vec![(3, 4)], "{:?}", encoding);
+            }
+            assert_eq!(
+                Phonetic::new(encoding).distance("12345", "---"),
+                f64::INFINITY
+            );
+        }
+    }
+
+    #[test]
+    fn blocking_matches_brute_force() {
+        let lines = names();
+
+        for encoding in [Encoding::Soundex, Encoding::Metaphone, Encoding::Nysiis]
+            .iter()
+            .cloned()
+        {
+            for algo in &[
+                Phonetic::new(encoding),
+                Phonetic::new(encoding).threshold(2),
+            ] {
+                assert_eq!(sorted(algo.pairs(&lines)), brute_force(algo, &lines));
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use super::min_hash::stable_hash;
+use super::token::tokens;
+use super::ClusterAlgo;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Clusters near-duplicate documents whose 64 bit SimHash fingerprints differ
+/// in fewer than a threshold of bits.
+///
+/// Each distinct token votes on every bit of the fingerprint according to its
+/// hash, weighted by how often it occurs. Tokens are runs of alphanumeric
+/// characters. Fingerprints are split into `threshold` blocks of bits; by the
+/// pigeonhole principle, fingerprints differing in fewer bits than there are
+/// blocks agree on some block exactly, so one lookup table per block yields
+/// every pair. Past 64 blocks every pair is compared.
+pub struct SimHash {
+    threshold: usize,
+}
+
+impl SimHash {
+    /// Accepts pairs whose fingerprints differ in fewer than `threshold` bits.
+    pub fn new(threshold: usize) -> SimHash {
+        SimHash { threshold }
+    }
+
+    /// The 64 bit fingerprint of `record`.
+    ///
+    /// Tokens are hashed with a fixed FNV-1a based hash, so fingerprints can
+    /// be stored and compared across builds.
+    pub fn fingerprint(record: &str) -> u64 {
+        let mut weights = HashMap::new();
+        for token in tokens(record) {
+            *weights.entry(token).or_insert(0i64) += 1;
+        }
+
+        let mut votes = [0i64; 64];
+        for (token, weight) in weights {
+            let hash = stable_hash(Some(token));
+
+            for (bit, vote) in votes.iter_mut().enumerate() {
+                if hash & (1 << bit) != 0 {
+                    *vote += weight;
+                } else {
+                    *vote -= weight;
+                }
+            }
+        }
+
+        votes
+            .iter()
+            .enumerate()
+            .filter(|&(_, &vote)| vote > 0)
+            .fold(0, |fingerprint, (bit, _)| fingerprint | (1 << bit))
+    }
+
+    fn bits(&self, first: &str, second: &str) -> usize {
+        (SimHash::fingerprint(first) ^ SimHash::fingerprint(second)).count_ones() as usize
+    }
+}
+
+impl ClusterAlgo for SimHash {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.bits(first, second) < self.threshold
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        self.bits(first, second) as f64
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.threshold as f64)
+    }
+
+    fn pairs(&self, lines: &[String]) -> Vec<(usize, usize)> {
+        let radius = match self.threshold.checked_sub(1) {
+            Some(radius) => radius,
+            None => return Vec::new(),
+        };
+
+        let fingerprints = lines
+            .par_iter()
+            .map(|line| SimHash::fingerprint(line))
+            .collect::<Vec<u64>>();
+
+        // Fingerprints within `radius` bits agree on one of `radius + 1` blocks.
+        let blocks = radius + 1;
+        if blocks > 64 {
+            return (0..lines.len())
+                .flat_map(|first| (first + 1..lines.len()).map(move |second| (first, second)))
+                .collect();
+        }
+
+        let masks = (0..blocks)
+            .map(|block| {
+                let (start, end) = (block * 64 / blocks, (block + 1) * 64 / blocks);
+                (start..end).fold(0u64, |mask, bit| mask | (1 << bit))
+            })
+            .collect::<Vec<u64>>();
+
+        let mut tables = vec![HashMap::new(); blocks];
+        for (index, fingerprint) in fingerprints.iter().enumerate() {
+            for (table, mask) in tables.iter_mut().zip(&masks) {
+                table
+                    .entry(fingerprint & mask)
+                    .or_insert_with(Vec::new)
+                    .push(index);
+            }
+        }
+
+        let candidates = tables
+            .iter()
+            .flat_map(|table| table.values())
+            .flat_map(|bucket| {
+                bucket.iter().enumerate().flat_map(move |(n, &first)| {
+                    bucket[n + 1..].iter().map(move |&second| (first, second))
+                })
+            })
+            .collect::<HashSet<(usize, usize)>>();
+
+        candidates
+            .into_par_iter()
+            .filter(|&(first, second)| {
+                ((fingerprints[first] ^ fingerprints[second]).count_ones() as usize) <= radius
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::test_util::{brute_force, paragraphs, sorted};
+
+    #[test]
+    fn fingerprints_are_pinned() {
+        assert_eq!(
+            SimHash::fingerprint("the quick brown fox"),
+            0x4424_0d6d_1218_021e
+        );
+        assert_eq!(SimHash::fingerprint("short"), 0x9ec7_da74_7162_aaf9);
+        assert_eq!(SimHash::fingerprint(""), 0);
+    }
+
+    #[test]
+    fn near_duplicates_share_bits() {
+        let lines = paragraphs();
+
+        assert_eq!(
+            SimHash::fingerprint(&lines[0]),
+            SimHash::fingerprint(&lines[3])
+        );
+        assert!(SimHash::new(12).accept(&lines[0], &lines[1]));
+        assert!(!SimHash::new(12).accept(&lines[0], &lines[2]));
+    }
+
+    #[test]
+    fn tables_match_brute_force() {
+        let lines = paragraphs();
+
+        for threshold in [0, 1, 4, 12, 32, 64, 65, 70].iter().cloned() {
+            let algo = SimHash::new(threshold);
+
+            assert_eq!(sorted(algo.pairs(&lines)), brute_force(&algo, &lines));
+        }
+    }
+}
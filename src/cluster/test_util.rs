@@ -0,0 +1,47 @@
+//! Fixtures and checks shared by the clustering tests.
+
+use super::{Cluster, ClusterAlgo};
+use crate::combinations::combinations;
+
+/// Owned records from string literals.
+pub(super) fn lines(records: &[&str]) -> Vec<String> {
+    records.iter().map(|s| s.to_string()).collect()
+}
+
+/// Paragraphs where the first, second and fourth are near-duplicates of each
+/// other, followed by records sharing no words with any of them.
+pub(super) fn paragraphs() -> Vec<String> {
+    lines(&[
+        "the quick brown fox jumps over the lazy dog and runs into the forest before dawn",
+        "the quick brown fox jumps over the lazy dog and runs into the forest before dusk",
+        "a completely different paragraph about clustering text records by their similarity",
+        "the quick brown fox jumps over the lazy dog and runs into the forest before dawn",
+        "short",
+        "",
+    ])
+}
+
+/// The records of each cluster in `cluster`, in output order.
+pub(super) fn sets(cluster: Cluster<'_>) -> Vec<Vec<&String>> {
+    cluster.into_iter().collect()
+}
+
+/// `pairs` in order, so pair lists compare regardless of how they were found.
+pub(super) fn sorted(mut pairs: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    pairs.sort();
+    pairs
+}
+
+/// Every pair of `lines` accepted by `algo`, in order, found by comparing all
+/// of them.
+pub(super) fn brute_force<A>(algo: &A, lines: &[String]) -> Vec<(usize, usize)>
+where
+    A: ClusterAlgo,
+{
+    let indices = (0..lines.len()).collect::<Vec<usize>>();
+
+    combinations(&indices)
+        .map(|(&first, &second)| (first, second))
+        .filter(|&(first, second)| algo.accept(&lines[first], &lines[second]))
+        .collect()
+}
@@ -0,0 +1,117 @@
+use super::ClusterAlgo;
+use std::collections::BTreeSet;
+use strsim::normalized_levenshtein;
+
+pub(super) fn tokens(record: &str) -> impl Iterator<Item = &str> {
+    record
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+}
+
+fn sorted(record: &str) -> String {
+    let mut tokens = tokens(record).collect::<Vec<&str>>();
+    tokens.sort_unstable();
+    tokens.join(" ")
+}
+
+/// Clusters records whose sorted tokens have a normalized levenshtein
+/// similarity above a ratio, so word order does not matter.
+///
+/// Tokens are runs of alphanumeric characters.
+pub struct TokenSort {
+    ratio: f64,
+}
+
+impl TokenSort {
+    /// Accepts pairs with a token sort similarity above `ratio`.
+    pub fn new(ratio: f64) -> TokenSort {
+        TokenSort { ratio }
+    }
+
+    fn similarity(&self, first: &str, second: &str) -> f64 {
+        normalized_levenshtein(&sorted(first), &sorted(second))
+    }
+}
+
+impl ClusterAlgo for TokenSort {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.similarity(first, second) > self.ratio
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        1.0 - self.similarity(first, second)
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(1.0 - self.ratio)
+    }
+}
+
+/// Clusters records by comparing their shared tokens with the shared tokens
+/// plus what remains of each, so duplicated and extra words matter less.
+///
+/// Tokens are runs of alphanumeric characters.
+pub struct TokenSet {
+    ratio: f64,
+}
+
+impl TokenSet {
+    /// Accepts pairs with a token set similarity above `ratio`.
+    pub fn new(ratio: f64) -> TokenSet {
+        TokenSet { ratio }
+    }
+
+    fn similarity(&self, first: &str, second: &str) -> f64 {
+        let first = tokens(first).collect::<BTreeSet<&str>>();
+        let second = tokens(second).collect::<BTreeSet<&str>>();
+
+        let shared = first.intersection(&second).cloned().collect::<Vec<&str>>();
+        let extend = |rest: Vec<&str>| {
+            let mut tokens = shared.clone();
+            tokens.extend(rest);
+            tokens.join(" ")
+        };
+
+        let base = shared.join(" ");
+        let with_first = extend(first.difference(&second).cloned().collect());
+        let with_second = extend(second.difference(&first).cloned().collect());
+
+        normalized_levenshtein(&base, &with_first)
+            .max(normalized_levenshtein(&base, &with_second))
+            .max(normalized_levenshtein(&with_first, &with_second))
+    }
+}
+
+impl ClusterAlgo for TokenSet {
+    fn accept(&self, first: &str, second: &str) -> bool {
+        self.similarity(first, second) > self.ratio
+    }
+
+    fn distance(&self, first: &str, second: &str) -> f64 {
+        1.0 - self.similarity(first, second)
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(1.0 - self.ratio)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn token_sort_ignores_order() {
+        assert!(TokenSort::new(0.99).accept("Smith, John", "John Smith"));
+        assert!(!TokenSort::new(0.8).accept("Smith, John", "Jane Smith"));
+    }
+
+    #[test]
+    fn token_set_ignores_extra_tokens() {
+        let (first, second) = ("Smith, John", "John Smith Jr. Jr.");
+
+        assert!(!TokenSort::new(0.7).accept(first, second));
+        assert!(TokenSet::new(0.99).accept(first, second));
+        assert!(!TokenSet::new(0.7).accept(first, "Jane Doe"));
+    }
+}
@@ -1,20 +1,27 @@
-use crate::cluster::Cluster;
+//! Writing clusters to an output stream.
+
+use crate::cluster::{Cluster, Dendrogram, Point};
+use std::collections::HashMap;
 use std::io::Write;
+use std::mem;
 
+/// Separates the members of a cluster.
 pub enum FieldSeperator {
     Null,
     Colon,
     Line,
 }
 
+/// Separates one cluster from the next.
 pub enum RecordSeperator {
     Null,
     Line,
+    /// A blank line between clusters.
     DLine,
 }
 
 impl FieldSeperator {
-    fn repr(&self) -> &str {
+    fn repr(&self) -> &'static str {
         match self {
             FieldSeperator::Null => "\0",
             FieldSeperator::Colon => ":",
@@ -24,7 +31,7 @@ impl FieldSeperator {
 }
 
 impl RecordSeperator {
-    fn repr(&self) -> &str {
+    fn repr(&self) -> &'static str {
         match self {
             RecordSeperator::Null => "\0",
             RecordSeperator::Line => "\n",
@@ -33,49 +40,560 @@ impl RecordSeperator {
     }
 }
 
+/// How clusters are laid out.
+pub enum Format {
+    /// Members joined by the field and record separators.
+    Text,
+    /// A single json array of cluster objects.
+    Json,
+    /// One json cluster object per line.
+    Ndjson,
+    /// Every record in input order prefixed by its cluster id and a tab,
+    /// and by the cluster size and another tab when `size` is set.
+    Label { size: bool },
+}
+
+/// How a dendrogram is laid out.
+pub enum Tree {
+    /// Newick with quoted leaf labels and branch lengths, leaving out the
+    /// lengths of branches to nodes at an infinite height.
+    Newick,
+    /// A json object with the `members` and `counts` of its leaves and its
+    /// `merges`, each with the `left` and `right` nodes joined, their
+    /// `height` and the `size` of the new node.
+    Json,
+}
+
+/// Writes clusters using the configured separators.
 pub struct ClusterOutput<'w, W: Write> {
     write: &'w mut W,
     ofs: FieldSeperator,
     ors: RecordSeperator,
+    format: Format,
+    representatives: bool,
+    templates: bool,
+    counts: bool,
 }
 
 impl<'w, W> ClusterOutput<'w, W>
 where
     W: Write,
 {
+    /// Writes to `write` separating members with `ofs` and clusters with `ors`.
     pub fn new(
         write: &'w mut W,
         ofs: FieldSeperator,
         ors: RecordSeperator,
     ) -> ClusterOutput<'w, W> {
-        ClusterOutput { write, ofs, ors }
+        ClusterOutput {
+            write,
+            ofs,
+            ors,
+            format: Format::Text,
+            representatives: false,
+            templates: false,
+            counts: false,
+        }
+    }
+
+    /// Lays clusters out as `format` instead of separated text.
+    ///
+    /// Structured formats ignore the separators and write one object per
+    /// cluster with its `id`, its `size` in records, its distinct `members`
+    /// with their `counts` and 1-based record `lines`, plus its
+    /// `representative` and `template` when they were derived.
+    pub fn format(mut self, format: Format) -> ClusterOutput<'w, W> {
+        self.format = format;
+        self
+    }
+
+    /// Writes only the representative of each cluster in text output.
+    ///
+    /// Clusters without chosen representatives fall back to their first member.
+    pub fn representatives(mut self, representatives: bool) -> ClusterOutput<'w, W> {
+        self.representatives = representatives;
+        self
+    }
+
+    /// Writes only the template of each cluster in text output.
+    ///
+    /// Clusters without derived templates fall back to their first member.
+    pub fn templates(mut self, templates: bool) -> ClusterOutput<'w, W> {
+        self.templates = templates;
+        self
+    }
+
+    /// Prefixes each text member with its number of occurrences and a space,
+    /// or each representative and template with the size of its cluster.
+    pub fn counts(mut self, counts: bool) -> ClusterOutput<'w, W> {
+        self.counts = counts;
+        self
+    }
+
+    /// Writes every cluster followed by a trailing newline.
+    ///
+    /// Records classified as [`Point::Noise`]
+    /// follow the clusters: text output writes them as one more record after
+    /// the last cluster, json and ndjson add an object like a cluster's with `"noise":true` in
+    /// place of its id, and labels write `-` for their id. Labels of
+    /// classified records also get a column with their `core`, `border` or
+    /// `noise` role.
+    pub fn output(&mut self, cluster: Cluster<'_>) {
+        match self.format {
+            Format::Text => self.output_text(cluster),
+            Format::Json => self.output_json(cluster),
+            Format::Ndjson => self.output_ndjson(cluster),
+            Format::Label { size } => self.output_label(cluster, size),
+        }
+    }
+
+    /// Writes that the record at 0-based `index` joined cluster `id`, now of
+    /// `size` records, and flushes so assignments appear as they are made.
+    ///
+    /// Json and ndjson write an object with the `id`, `size`, 1-based `line`
+    /// and `record` on its own line, while text and labels write the id,
+    /// then the size if labels ask for it, then the record.
+    pub fn output_assignment(&mut self, index: usize, id: usize, size: usize, record: &str) {
+        match self.format {
+            Format::Json | Format::Ndjson => {
+                let _ = writeln!(
+                    self.write,
+                    "{{\"id\":{},\"size\":{},\"line\":{},\"record\":{}}}",
+                    id,
+                    size,
+                    index + 1,
+                    json_string(record)
+                );
+            }
+
+            Format::Label { size: true } => {
+                let _ = writeln!(self.write, "{}\t{}\t{}", id, size, record);
+            }
+
+            Format::Text | Format::Label { size: false } => {
+                let _ = writeln!(self.write, "{}\t{}", id, record);
+            }
+        }
+
+        let _ = self.write.flush();
+    }
+
+    /// Writes the clusters of several cuts of one hierarchy, each labelled with
+    /// its height.
+    ///
+    /// Text output puts a `# cut <height>` record before each cut, json wraps
+    /// each cut's clusters in an object with its `cut` and ndjson adds the
+    /// `cut` to every cluster object. Labels get a column per cut, with `-`
+    /// for records not selected at that cut.
+    pub fn output_cuts(&mut self, cuts: Vec<(f64, Cluster<'_>)>) {
+        match self.format {
+            Format::Text => {
+                let mut csep = "";
+                for (height, cluster) in cuts {
+                    let _ = write!(self.write, "{}# cut {}", csep, height);
+                    self.output_records(&cluster, self.ors.repr());
+                    csep = self.ors.repr();
+                }
+                let _ = writeln!(self.write);
+            }
+
+            Format::Json => {
+                let _ = write!(self.write, "[");
+
+                let mut csep = "\n";
+                for (height, cluster) in cuts {
+                    let objects = cluster
+                        .groups()
+                        .map(|(id, group)| json_object(&cluster, id, group))
+                        .collect::<Vec<String>>()
+                        .join(",\n");
+
+                    let _ = write!(
+                        self.write,
+                        "{}{{\"cut\":{},\"clusters\":[\n{}\n]}}",
+                        csep,
+                        json_number(height),
+                        objects
+                    );
+                    csep = ",\n";
+                }
+
+                let _ = writeln!(self.write, "\n]");
+            }
+
+            Format::Ndjson => {
+                for (height, cluster) in cuts {
+                    for (id, group) in cluster.groups() {
+                        let object = json_object(&cluster, id, group);
+                        let _ = writeln!(
+                            self.write,
+                            "{{\"cut\":{},{}",
+                            json_number(height),
+                            &object[1..]
+                        );
+                    }
+                }
+            }
+
+            Format::Label { size } => self.output_cut_labels(cuts, size),
+        }
+    }
+
+    fn output_cut_labels(&mut self, cuts: Vec<(f64, Cluster<'_>)>, size: bool) {
+        let columns = cuts
+            .iter()
+            .map(|(_, cluster)| {
+                let sizes = cluster
+                    .groups()
+                    .map(|(id, group)| (id, group.len()))
+                    .collect::<HashMap<usize, usize>>();
+
+                (cluster.labels(), sizes)
+            })
+            .collect::<Vec<(Vec<Option<usize>>, HashMap<usize, usize>)>>();
+
+        let records = match cuts.first() {
+            Some((_, cluster)) => cluster.labels().len(),
+            None => return,
+        };
+
+        for index in 0..records {
+            if columns.iter().all(|(labels, _)| labels[index].is_none()) {
+                continue;
+            }
+
+            for (labels, sizes) in &columns {
+                match labels[index] {
+                    Some(id) if size => {
+                        let _ = write!(self.write, "{}\t{}\t", id, sizes[&id]);
+                    }
+                    Some(id) => {
+                        let _ = write!(self.write, "{}\t", id);
+                    }
+                    None if size => {
+                        let _ = write!(self.write, "-\t-\t");
+                    }
+                    None => {
+                        let _ = write!(self.write, "-\t");
+                    }
+                }
+            }
+
+            let _ = writeln!(self.write, "{}", cuts[0].1.record(index));
+        }
+    }
+
+    /// Writes `dendrogram` laid out as `tree`, followed by a newline.
+    pub fn output_dendrogram(&mut self, dendrogram: &Dendrogram<'_>, tree: Tree) {
+        match tree {
+            Tree::Newick => {
+                let _ = writeln!(self.write, "{};", newick(dendrogram));
+            }
+
+            Tree::Json => {
+                let members = (0..dendrogram.leaves())
+                    .map(|leaf| json_string(dendrogram.leaf(leaf)))
+                    .collect::<Vec<String>>()
+                    .join(",");
+
+                let counts = dendrogram
+                    .counts()
+                    .iter()
+                    .map(|count| count.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",");
+
+                let merges = dendrogram
+                    .merges()
+                    .iter()
+                    .map(|merge| {
+                        format!(
+                            "{{\"left\":{},\"right\":{},\"height\":{},\"size\":{}}}",
+                            merge.left,
+                            merge.right,
+                            json_number(merge.height),
+                            merge.size
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join(",");
+
+                let _ = writeln!(
+                    self.write,
+                    "{{\"members\":[{}],\"counts\":[{}],\"merges\":[{}]}}",
+                    members, counts, merges
+                );
+            }
+        }
+    }
+
+    fn output_text(&mut self, cluster: Cluster<'_>) {
+        self.output_records(&cluster, "");
+        let _ = writeln!(self.write);
     }
 
-    pub fn output<'a>(&mut self, cluster: Cluster<'a>) {
-        let ofs = self.ofs.repr();
+    /// Writes the text records of `cluster`, each preceded by the record
+    /// separator except the first, which is preceded by `rsep`.
+    fn output_records(&mut self, cluster: &Cluster<'_>, mut rsep: &'static str) {
         let ors = self.ors.repr();
 
-        let mut rsep = "";
-        for set in cluster {
+        if self.templates || self.representatives {
+            for (_, group) in cluster.groups() {
+                let exemplar = if self.templates {
+                    cluster.template(group)
+                } else {
+                    cluster
+                        .representative(group)
+                        .map(|rep| cluster.record(rep).as_str())
+                };
+
+                let exemplar = exemplar.unwrap_or_else(|| cluster.record(group[0]));
+                let _ = write!(self.write, "{}", rsep);
+                if self.counts {
+                    let _ = write!(self.write, "{} ", group.len());
+                }
+                let _ = write!(self.write, "{}", exemplar);
+
+                rsep = ors;
+            }
+        } else {
+            for (_, group) in cluster.groups() {
+                let _ = write!(self.write, "{}", rsep);
+                self.output_members(cluster, group);
+
+                rsep = ors;
+            }
+        }
+
+        let noise = cluster.noise();
+        if !noise.is_empty() {
             let _ = write!(self.write, "{}", rsep);
-            let mut fsep = "";
-            for r in set {
-                let _ = write!(self.write, "{}", fsep);
-                let _ = write!(self.write, "{}", r);
+            self.output_members(cluster, &noise);
+        }
+    }
+
+    fn output_members(&mut self, cluster: &Cluster<'_>, group: &[usize]) {
+        let mut fsep = "";
+        for (r, indices) in cluster.members(group) {
+            let _ = write!(self.write, "{}", fsep);
+            if self.counts {
+                let _ = write!(self.write, "{} ", indices.len());
+            }
+            let _ = write!(self.write, "{}", r);
+
+            fsep = self.ofs.repr();
+        }
+    }
+
+    fn output_json(&mut self, cluster: Cluster<'_>) {
+        let _ = write!(self.write, "[");
+
+        let mut rsep = "\n";
+        for (id, group) in cluster.groups() {
+            let _ = write!(self.write, "{}{}", rsep, json_object(&cluster, id, group));
+            rsep = ",\n";
+        }
+
+        let noise = cluster.noise();
+        if !noise.is_empty() {
+            let _ = write!(self.write, "{}{}", rsep, json_noise(&cluster, &noise));
+        }
+
+        let _ = writeln!(self.write, "\n]");
+    }
 
-                fsep = ofs;
+    fn output_ndjson(&mut self, cluster: Cluster<'_>) {
+        for (id, group) in cluster.groups() {
+            let _ = writeln!(self.write, "{}", json_object(&cluster, id, group));
+        }
+
+        let noise = cluster.noise();
+        if !noise.is_empty() {
+            let _ = writeln!(self.write, "{}", json_noise(&cluster, &noise));
+        }
+    }
+
+    fn output_label(&mut self, cluster: Cluster<'_>, size: bool) {
+        let sizes = cluster
+            .groups()
+            .map(|(id, group)| (id, group.len()))
+            .collect::<HashMap<usize, usize>>();
+
+        for (index, label) in cluster.labels().into_iter().enumerate() {
+            let point = cluster.point(index);
+            match label {
+                Some(id) if size => {
+                    let _ = write!(self.write, "{}\t{}\t", id, sizes[&id]);
+                }
+                Some(id) => {
+                    let _ = write!(self.write, "{}\t", id);
+                }
+                None if point == Some(Point::Noise) && size => {
+                    let _ = write!(self.write, "-\t-\t");
+                }
+                None if point == Some(Point::Noise) => {
+                    let _ = write!(self.write, "-\t");
+                }
+                None => continue,
             }
 
-            rsep = ors;
+            match point {
+                Some(Point::Core) => {
+                    let _ = write!(self.write, "core\t");
+                }
+                Some(Point::Border) => {
+                    let _ = write!(self.write, "border\t");
+                }
+                Some(Point::Noise) => {
+                    let _ = write!(self.write, "noise\t");
+                }
+                None => {}
+            }
+
+            let _ = writeln!(self.write, "{}", cluster.record(index));
         }
+    }
+}
+
+fn json_object(cluster: &Cluster<'_>, id: usize, group: &[usize]) -> String {
+    let representative = match cluster.representative(group) {
+        Some(rep) => format!(",\"representative\":{}", json_string(cluster.record(rep))),
+        None => String::new(),
+    };
+
+    let template = match cluster.template(group) {
+        Some(template) => format!(",\"template\":{}", json_string(template)),
+        None => String::new(),
+    };
+
+    format!(
+        "{{\"id\":{},\"size\":{}{}{},{}}}",
+        id,
+        group.len(),
+        representative,
+        template,
+        json_members(cluster, group)
+    )
+}
+
+fn json_noise(cluster: &Cluster<'_>, noise: &[usize]) -> String {
+    format!(
+        "{{\"noise\":true,\"size\":{},{}}}",
+        noise.len(),
+        json_members(cluster, noise)
+    )
+}
+
+/// The `members`, `counts` and `lines` fields of the records in `group`.
+fn json_members(cluster: &Cluster<'_>, group: &[usize]) -> String {
+    let distinct = cluster.members(group);
+
+    let members = distinct
+        .iter()
+        .map(|(record, _)| json_string(record))
+        .collect::<Vec<String>>()
+        .join(",");
+
+    let counts = distinct
+        .iter()
+        .map(|(_, indices)| indices.len().to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+
+    let lines = distinct
+        .iter()
+        .map(|(_, indices)| {
+            let lines = indices
+                .iter()
+                .map(|i| (i + 1).to_string())
+                .collect::<Vec<String>>()
+                .join(",");
+
+            format!("[{}]", lines)
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+
+    format!(
+        "\"members\":[{}],\"counts\":[{}],\"lines\":[{}]",
+        members, counts, lines
+    )
+}
+
+/// The newick tree of `dendrogram` without its terminating semicolon.
+fn newick(dendrogram: &Dendrogram<'_>) -> String {
+    let leaves = dendrogram.leaves();
+    let mut nodes = (0..leaves)
+        .map(|leaf| (newick_label(dendrogram.leaf(leaf)), 0.0))
+        .collect::<Vec<(String, f64)>>();
+
+    for merge in dendrogram.merges() {
+        let (left, left_height) = mem::take(&mut nodes[merge.left]);
+        let (right, right_height) = mem::take(&mut nodes[merge.right]);
+
+        nodes.push((
+            format!(
+                "({}{},{}{})",
+                left,
+                newick_length(merge.height - left_height),
+                right,
+                newick_length(merge.height - right_height)
+            ),
+            merge.height,
+        ));
+    }
+
+    nodes.pop().map(|(node, _)| node).unwrap_or_default()
+}
+
+/// A branch length, left out when a height is not finite.
+fn newick_length(length: f64) -> String {
+    if length.is_finite() {
+        format!(":{}", length)
+    } else {
+        String::new()
+    }
+}
+
+fn newick_label(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
 
-        let _ = write!(self.write, "\n");
+fn json_number(n: f64) -> String {
+    if n.is_finite() {
+        n.to_string()
+    } else {
+        "null".to_string()
     }
 }
 
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::cluster::{
+        ClusterAlgo, Dendrogram, Hamming, Levenshtein, Linkage, Representative, Singletons,
+    };
+    use crate::mask::Masker;
 
     use super::FieldSeperator as FS;
     use super::RecordSeperator as RS;
@@ -110,6 +628,150 @@ mod test {
         }};
     }
 
+    #[test]
+    fn json_escapes_members() {
+        let lines = vec![
+            "say \"hi\"".to_string(),
+            "a:b\nc".to_string(),
+            "x".to_string(),
+        ];
+        let cluster = Cluster::from_pairs(&lines, vec![(0, 1)]);
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Line, RS::DLine)
+            .format(Format::Json)
+            .output(cluster);
+
+        assert_eq!(
+            String::from_utf8_lossy(&buf),
+            "[\n{\"id\":0,\"size\":2,\"members\":[\"say \\\"hi\\\"\",\"a:b\\nc\"],\"counts\":[1,1],\"lines\":[[1],[2]]}\n]\n"
+        );
+    }
+
+    #[test]
+    fn ndjson_object_per_line() {
+        let lines = vec!["a".to_string(), "b".to_string(), "\u{1}".to_string()];
+        let cluster = Cluster::from_pairs(&lines, vec![]).singletons(Singletons::Include);
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Line, RS::DLine)
+            .format(Format::Ndjson)
+            .output(cluster);
+
+        let result = String::from_utf8_lossy(&buf);
+        let objects = result.lines().collect::<Vec<&str>>();
+
+        assert_eq!(objects.len(), 3);
+        assert_eq!(
+            objects[2],
+            "{\"id\":2,\"size\":1,\"members\":[\"\\u0001\"],\"counts\":[1],\"lines\":[[3]]}"
+        );
+    }
+
+    #[test]
+    fn representatives_only() {
+        let lines = vec![
+            "hello".to_string(),
+            "help".to_string(),
+            "sweet".to_string(),
+            "sweat".to_string(),
+        ];
+        let algo = Levenshtein::new(3);
+        let cluster = algo
+            .cluster(&lines)
+            .representatives(Representative::Shortest, &algo);
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Line, RS::Line)
+            .representatives(true)
+            .output(cluster);
+
+        assert_eq!(String::from_utf8_lossy(&buf), "help\nsweet\n");
+    }
+
+    #[test]
+    fn templates_only() {
+        let lines = vec![
+            "took 12ms".to_string(),
+            "took 140ms".to_string(),
+            "done".to_string(),
+        ];
+        let keys = Masker::all().masks(&lines);
+        let cluster = Levenshtein::new(1)
+            .cluster_by(&lines, &keys)
+            .singletons(Singletons::Include)
+            .templates(&keys);
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Line, RS::Line)
+            .templates(true)
+            .output(cluster);
+
+        assert_eq!(String::from_utf8_lossy(&buf), "took <NUM>ms\ndone\n");
+    }
+
+    #[test]
+    fn json_representative_field() {
+        let lines = vec!["hello".to_string(), "help".to_string()];
+        let algo = Levenshtein::new(3);
+        let cluster = algo
+            .cluster(&lines)
+            .representatives(Representative::First, &algo);
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Line, RS::Line)
+            .format(Format::Ndjson)
+            .output(cluster);
+
+        assert_eq!(
+            String::from_utf8_lossy(&buf),
+            "{\"id\":0,\"size\":2,\"representative\":\"hello\",\"members\":[\"hello\",\"help\"],\"counts\":[1,1],\"lines\":[[1],[2]]}\n"
+        );
+    }
+
+    #[test]
+    fn counts_duplicates() {
+        let lines = vec![
+            "hello".to_string(),
+            "help".to_string(),
+            "hello".to_string(),
+            "x".to_string(),
+        ];
+        let cluster = Levenshtein::new(3).cluster(&lines);
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Colon, RS::Line)
+            .counts(true)
+            .output(cluster);
+
+        assert_eq!(String::from_utf8_lossy(&buf), "2 hello:1 help\n");
+
+        let cluster = Levenshtein::new(3).cluster(&lines);
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Colon, RS::Line)
+            .format(Format::Ndjson)
+            .output(cluster);
+
+        assert_eq!(
+            String::from_utf8_lossy(&buf),
+            "{\"id\":0,\"size\":3,\"members\":[\"hello\",\"help\"],\"counts\":[2,1],\"lines\":[[1,3],[2]]}\n"
+        );
+    }
+
+    #[test]
+    fn label_keeps_input_order() {
+        let lines = vec!["a".to_string(), "b".to_string(), "a".to_string()];
+        let cluster = Cluster::from_pairs(&lines, vec![(0, 2)]).singletons(Singletons::Include);
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Line, RS::DLine)
+            .format(Format::Label { size: true })
+            .output(cluster);
+
+        assert_eq!(String::from_utf8_lossy(&buf), "0\t2\ta\n1\t1\tb\n0\t2\ta\n");
+    }
+
     #[test]
     fn default_line_dline() {
         let a = "hello".to_string();
@@ -181,4 +843,123 @@ mod test {
         assert_split_contains!(lines[1], ":", &c);
         assert_split_contains!(lines[1], ":", &d);
     }
+
+    #[test]
+    fn dendrogram_trees() {
+        let lines = vec!["it's".to_string(), "its".to_string(), "zzzz".to_string()];
+        let dendrogram = Dendrogram::new(&Levenshtein::new(1), &lines, &lines, Linkage::Single);
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Line, RS::DLine)
+            .output_dendrogram(&dendrogram, Tree::Newick);
+        assert_eq!(
+            String::from_utf8_lossy(&buf),
+            "('zzzz':4,('its':1,'it''s':1):3);\n"
+        );
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Line, RS::DLine)
+            .output_dendrogram(&dendrogram, Tree::Json);
+        assert_eq!(
+            String::from_utf8_lossy(&buf),
+            "{\"members\":[\"it's\",\"its\",\"zzzz\"],\"counts\":[1,1,1],\"merges\":[\
+             {\"left\":1,\"right\":0,\"height\":1,\"size\":2},\
+             {\"left\":2,\"right\":3,\"height\":4,\"size\":3}]}\n"
+        );
+
+        let lines = vec!["ab".to_string(), "xyz".to_string(), "xyw".to_string()];
+        let dendrogram = Dendrogram::new(&Hamming::new(2), &lines, &lines, Linkage::Single);
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Line, RS::DLine)
+            .output_dendrogram(&dendrogram, Tree::Newick);
+        assert_eq!(String::from_utf8_lossy(&buf), "(('xyw':1,'xyz':1),'ab');\n");
+    }
+
+    #[test]
+    fn text_per_cut() {
+        let lines = ["aaaa", "aaab", "abbb", "zzzz"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        let dendrogram = Dendrogram::new(&Levenshtein::new(1), &lines, &lines, Linkage::Single);
+        let cuts = vec![(1.5, dendrogram.cut(1.5)), (2.5, dendrogram.cut(2.5))];
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Colon, RS::Null).output_cuts(cuts);
+        assert_eq!(
+            String::from_utf8_lossy(&buf),
+            "# cut 1.5\0aaaa:aaab\0# cut 2.5\0aaaa:aaab:abbb\n"
+        );
+    }
+
+    #[test]
+    fn labels_per_cut() {
+        let lines = ["aaaa", "aaab", "abbb", "zzzz"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        let dendrogram = Dendrogram::new(&Levenshtein::new(1), &lines, &lines, Linkage::Single);
+        let cuts = vec![(1.5, dendrogram.cut(1.5)), (2.5, dendrogram.cut(2.5))];
+
+        let mut buf = Vec::new();
+        ClusterOutput::new(&mut buf, FS::Line, RS::DLine)
+            .format(Format::Label { size: false })
+            .output_cuts(cuts);
+        assert_eq!(
+            String::from_utf8_lossy(&buf),
+            "0\t0\taaaa\n0\t0\taaab\n-\t0\tabbb\n"
+        );
+    }
+
+    #[test]
+    fn noise_follows_clusters() {
+        let lines = ["abc", "abd", "xyz", "abc"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        let output = |format, representatives, ors| {
+            let mut buf = Vec::new();
+            ClusterOutput::new(&mut buf, FS::Colon, ors)
+                .format(format)
+                .representatives(representatives)
+                .output(Levenshtein::new(2).cluster_dense(&lines, &lines, 3));
+            String::from_utf8(buf).unwrap()
+        };
+
+        assert_eq!(output(Format::Text, false, RS::Line), "abc:abd\nxyz\n");
+        assert_eq!(output(Format::Text, true, RS::Line), "abc\nxyz\n");
+        assert_eq!(output(Format::Text, false, RS::Null), "abc:abd\0xyz\n");
+        assert_eq!(
+            output(Format::Ndjson, false, RS::Line),
+            "{\"id\":0,\"size\":3,\"members\":[\"abc\",\"abd\"],\"counts\":[2,1],\"lines\":[[1,4],[2]]}\n\
+             {\"noise\":true,\"size\":1,\"members\":[\"xyz\"],\"counts\":[1],\"lines\":[[3]]}\n"
+        );
+        assert_eq!(
+            output(Format::Label { size: false }, false, RS::Line),
+            "0\tcore\tabc\n0\tcore\tabd\n-\tnoise\txyz\n0\tcore\tabc\n"
+        );
+    }
+
+    #[test]
+    fn assignments_per_format() {
+        let output = |format| {
+            let mut buf = Vec::new();
+            let mut out = ClusterOutput::new(&mut buf, FS::Line, RS::DLine).format(format);
+            out.output_assignment(0, 0, 1, "say \"hi\"");
+            out.output_assignment(1, 0, 2, "say hi");
+            String::from_utf8(buf).unwrap()
+        };
+
+        assert_eq!(output(Format::Text), "0\tsay \"hi\"\n0\tsay hi\n");
+        assert_eq!(
+            output(Format::Label { size: true }),
+            "0\t1\tsay \"hi\"\n0\t2\tsay hi\n"
+        );
+        assert_eq!(
+            output(Format::Ndjson),
+            "{\"id\":0,\"size\":1,\"line\":1,\"record\":\"say \\\"hi\\\"\"}\n\
+             {\"id\":0,\"size\":2,\"line\":2,\"record\":\"say hi\"}\n"
+        );
+    }
 }
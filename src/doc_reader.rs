@@ -1,11 +1,18 @@
+//! Splitting input streams into records.
+
 use std::io::{BufRead, BufReader, Read};
 
+/// How input records are delimited.
 pub enum RecordSeperator {
+    /// Records end at a null byte.
     Null,
+    /// Records end at a newline.
     Line,
+    /// Records are runs of non-blank lines.
     Paragraph,
 }
 
+/// Iterates over the records of a buffered reader.
 pub struct DocReader<R>
 where
     R: BufRead,
@@ -19,9 +26,10 @@ impl<R> DocReader<R>
 where
     R: BufRead,
 {
+    /// Reads records from an already buffered reader.
     pub fn new(read: R, irs: RecordSeperator) -> DocReader<R> {
         DocReader {
-            read: read,
+            read,
             buf: Vec::with_capacity(4096),
             irs,
         }
@@ -34,7 +42,7 @@ where
                     self.buf.pop();
                 }
 
-                Some(String::from(String::from_utf8_lossy(&self.buf).to_owned()))
+                Some(String::from_utf8_lossy(&self.buf).into_owned())
             } else {
                 None
             }
@@ -48,6 +56,7 @@ impl<R> DocReader<BufReader<R>>
 where
     R: Read,
 {
+    /// Reads records from an unbuffered reader.
     pub fn with_read(read: R, irs: RecordSeperator) -> DocReader<BufReader<R>> {
         DocReader {
             read: BufReader::new(read),
@@ -76,7 +85,7 @@ where
                 while let Some(line) = self.consume_to(10u8) {
                     self.buf.clear();
                     if line.is_empty() {
-                        if last_line == None {
+                        if last_line.is_none() {
                             continue;
                         } else {
                             break;
@@ -136,7 +145,7 @@ mod test {
     #[test]
     fn test_null_seperator() {
         let buffer = b"hello\0world\0null\0seperated\0docs";
-        let strs = vec![
+        let strs = [
             String::from("hello"),
             String::from("world"),
             String::from("null"),
@@ -155,7 +164,7 @@ mod test {
     #[test]
     fn test_line_seperator() {
         let buffer = b"hello\nworld\nnull\nseperated\ndocs";
-        let strs = vec![
+        let strs = [
             String::from("hello"),
             String::from("world"),
             String::from("null"),
@@ -175,7 +184,7 @@ mod test {
     #[test]
     fn test_paragraph_seperator() {
         let buffer = b"hello\nworld\n\nnull\nseperated\n\ndocs";
-        let strs = vec![
+        let strs = [
             String::from("hello\nworld"),
             String::from("null\nseperated"),
             String::from("docs"),
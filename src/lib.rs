@@ -0,0 +1,29 @@
+//! Similarity based text clustering.
+//!
+//! Records are read with a [`DocReader`], grouped by a [`ClusterAlgo`] into a
+//! [`Cluster`] and written back out with a [`ClusterOutput`].
+//!
+//! ```
+//! use txt_cluster::cluster::Levenshtein;
+//! use txt_cluster::doc_reader::RecordSeperator;
+//! use txt_cluster::{ClusterAlgo, DocReader};
+//!
+//! let input = b"hello\nhallo\nworld" as &[u8];
+//! let lines = DocReader::new(input, RecordSeperator::Line).collect::<Vec<String>>();
+//!
+//! let clusters = Levenshtein::new(2).cluster(&lines);
+//! assert_eq!(clusters.into_iter().count(), 1);
+//! ```
+
+pub mod cluster;
+pub mod cluster_output;
+pub mod doc_reader;
+pub mod mask;
+pub mod normalize;
+pub mod phonetic;
+
+mod combinations;
+
+pub use cluster::{Cluster, ClusterAlgo};
+pub use cluster_output::ClusterOutput;
+pub use doc_reader::DocReader;
@@ -1,15 +1,17 @@
 #[macro_use(crate_version, crate_name)]
 extern crate clap;
 
-mod cluster;
-mod cluster_output;
-mod combinations;
-mod doc_reader;
+mod cli;
 mod utils;
 mod validation;
 
 use clap::{App, Arg, SubCommand};
-use cluster::ClusterAlgo;
+use cli::FromMatches;
+use txt_cluster::cluster::{
+    Cosine, Dice, Hamming, Jaccard, Jaro, Levenshtein, NormLevenshtein, Phonetic, SimHash,
+    TokenSet, TokenSort, OSA,
+};
+use utils::{run_cluster, run_fitted, run_metric};
 use validation::*;
 
 fn main() {
@@ -41,7 +43,6 @@ fn main() {
 
     let ors_arg = Arg::with_name("ors")
         .help("output record seperator")
-        .short("r")
         .long("ors")
         .possible_values(&["0", "line", "l", "double", "d"])
         .takes_value(true);
@@ -52,6 +53,116 @@ fn main() {
         .possible_values(&["0", ":", "line", "l"])
         .takes_value(true);
 
+    let format_arg = Arg::with_name("format")
+        .help("output format")
+        .long("format")
+        .possible_values(&["text", "json", "ndjson", "label"])
+        .takes_value(true);
+
+    let size_arg = Arg::with_name("size")
+        .help("prefix labels with the cluster size")
+        .short("c")
+        .long("size");
+
+    let counts_arg = Arg::with_name("counts")
+        .help("prefix members with their number of occurrences")
+        .short("C")
+        .long("counts");
+
+    let sort_arg = Arg::with_name("sort")
+        .help("order of clusters and their members")
+        .long("sort")
+        .possible_values(&["first", "size", "lex"])
+        .takes_value(true);
+
+    let linkage_arg = Arg::with_name("linkage")
+        .help("whether one accepted pair, every pair or the average pair joins clusters [default: single]")
+        .long("linkage")
+        .possible_values(&["single", "complete", "average"])
+        .takes_value(true);
+
+    let cut_arg = Arg::with_name("cut")
+        .help("build the full hierarchy and cut it below each of these heights")
+        .long("cut")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .use_delimiter(true)
+        .conflicts_with("bands")
+        .validator(nonnegative_float);
+
+    let dendrogram_arg = Arg::with_name("dendrogram")
+        .help("build the full hierarchy and print it instead of clusters")
+        .long("dendrogram")
+        .possible_values(&["newick", "json"])
+        .takes_value(true)
+        .conflicts_with_all(&["cut", "bands"]);
+
+    let min_points_arg = Arg::with_name("min-points")
+        .help("cluster by density, only chaining records with this many accepted records nearby and reporting isolated ones as noise")
+        .long("min-points")
+        .takes_value(true)
+        .conflicts_with_all(&["linkage", "cut", "dendrogram"])
+        .validator(positive);
+
+    let medoids_arg = Arg::with_name("medoids")
+        .help("split into exactly this many clusters around medoids, each listed first, ignoring acceptance")
+        .long("medoids")
+        .takes_value(true)
+        .conflicts_with_all(&["linkage", "cut", "dendrogram", "min-points", "bands"])
+        .validator(positive);
+
+    let samples_arg = Arg::with_name("samples")
+        .help("medoid searches on samples of large inputs [default: 5]")
+        .long("samples")
+        .takes_value(true)
+        .requires("medoids")
+        .validator(positive);
+
+    let sample_size_arg = Arg::with_name("sample-size")
+        .help("distinct records per medoid sample [default: 40 + 2 * medoids, at least 500]")
+        .long("sample-size")
+        .takes_value(true)
+        .requires("medoids")
+        .validator(positive);
+
+    let stream_arg = Arg::with_name("stream")
+        .help("assign each record as it arrives to the closest accepting leader, or make it a new leader")
+        .long("stream")
+        .conflicts_with_all(&[
+            "linkage",
+            "cut",
+            "dendrogram",
+            "min-points",
+            "medoids",
+            "bands",
+            "sort",
+            "singletons",
+            "representative",
+            "representatives-only",
+            "templates",
+            "counts",
+        ]);
+
+    let representative_arg = Arg::with_name("representative")
+        .help("how to choose an exemplar for each cluster")
+        .short("p")
+        .long("representative")
+        .possible_values(&["medoid", "frequent", "shortest", "first"])
+        .takes_value(true);
+
+    let representatives_only_arg = Arg::with_name("representatives-only")
+        .help("only print each cluster's exemplar (medoid unless --representative)")
+        .short("P")
+        .long("representatives-only");
+
+    let singletons_arg = Arg::with_name("singletons")
+        .help("how to report records that matched nothing [default: exclude, include for labels]")
+        .short("s")
+        .long("singletons")
+        .possible_values(&["exclude", "include", "only"])
+        .takes_value(true);
+
     let threshold_arg = Arg::with_name("threshold")
         .help("maximum edit difference")
         .short("t")
@@ -60,11 +171,160 @@ fn main() {
         .takes_value(true)
         .validator(nonnegative);
 
+    let bits_arg = Arg::with_name("threshold")
+        .help("maximum differing fingerprint bits")
+        .short("t")
+        .long("threshold")
+        .required(true)
+        .takes_value(true)
+        .validator(nonnegative);
+
+    let encoding_arg = Arg::with_name("encoding")
+        .help("phonetic encoding [default: metaphone]")
+        .short("e")
+        .long("encoding")
+        .possible_values(&["soundex", "metaphone", "nysiis"])
+        .takes_value(true);
+
+    let code_threshold_arg = Arg::with_name("threshold")
+        .help("maximum edit difference between codes, instead of equal codes")
+        .short("t")
+        .long("threshold")
+        .takes_value(true)
+        .validator(nonnegative);
+
+    let index_arg = Arg::with_name("index")
+        .help("how to find candidate pairs")
+        .short("i")
+        .long("index")
+        .possible_values(&["brute", "bk-tree"])
+        .takes_value(true)
+        .conflicts_with("bands");
+
+    let unequal_arg = Arg::with_name("unequal")
+        .help("how to compare records of different lengths")
+        .short("u")
+        .long("unequal")
+        .possible_values(&["infinite", "pad"])
+        .takes_value(true);
+
+    let qgram_arg = Arg::with_name("qgram")
+        .help("characters per gram [default: 2]")
+        .short("q")
+        .long("qgram")
+        .takes_value(true)
+        .validator(positive);
+
+    let bands_arg = Arg::with_name("bands")
+        .help("find candidates by minhash lsh with this many bands, missing some pairs")
+        .short("b")
+        .long("bands")
+        .takes_value(true)
+        .validator(positive);
+
+    let rows_arg = Arg::with_name("rows")
+        .help("minhash rows per band [default: 4]")
+        .long("rows")
+        .takes_value(true)
+        .requires("bands")
+        .validator(positive);
+
+    let shingle_arg = Arg::with_name("shingle")
+        .help("words per minhash shingle [default: 3]")
+        .long("shingle")
+        .takes_value(true)
+        .requires("bands")
+        .validator(positive);
+
+    let tokenize_arg = Arg::with_name("tokenize")
+        .help("how to split records into tokens [default: alphanumeric]")
+        .long("tokenize")
+        .possible_values(&["alphanumeric", "whitespace"])
+        .takes_value(true);
+
+    let stopwords_arg = Arg::with_name("stopwords")
+        .help("ignore common english words")
+        .long("stopwords");
+
+    let stopwords_file_arg = Arg::with_name("stopwords-file")
+        .help("ignore the whitespace separated words in this file")
+        .long("stopwords-file")
+        .takes_value(true);
+
     let damerau_arg = Arg::with_name("damerau")
         .help("use damerau levenshtein")
         .short("d")
         .long("damerau");
 
+    let mask_arg = Arg::with_name("mask")
+        .help("mask variable tokens before comparing")
+        .short("m")
+        .long("mask")
+        .possible_values(&["all", "uuid", "date", "ip", "hex", "num"])
+        .takes_value(true)
+        .multiple(true)
+        .use_delimiter(true);
+
+    let mask_pattern_arg = Arg::with_name("mask-pattern")
+        .help("mask matches of this regex as <VAR> before comparing")
+        .long("mask-pattern")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .validator(regex);
+
+    let normalize_arg = Arg::with_name("normalize")
+        .help("normalize records before comparing")
+        .short("N")
+        .long("normalize")
+        .possible_values(&[
+            "all",
+            "nfc",
+            "nfkc",
+            "diacritics",
+            "lower",
+            "punctuation",
+            "whitespace",
+            "trim",
+        ])
+        .takes_value(true)
+        .multiple(true)
+        .use_delimiter(true);
+
+    let templates_arg = Arg::with_name("templates")
+        .help("only print each cluster's template")
+        .short("T")
+        .long("templates");
+
+    let common_args = [
+        file_arg,
+        irs_arg,
+        ors_arg,
+        ofs_arg,
+        singletons_arg,
+        format_arg,
+        size_arg,
+        counts_arg,
+        sort_arg,
+        linkage_arg,
+        cut_arg,
+        dendrogram_arg,
+        min_points_arg,
+        medoids_arg,
+        samples_arg,
+        sample_size_arg,
+        stream_arg,
+        representative_arg,
+        representatives_only_arg,
+        mask_arg,
+        mask_pattern_arg,
+        normalize_arg,
+        templates_arg,
+        bands_arg,
+        rows_arg,
+        shingle_arg,
+    ];
+
     let matches = App::new(crate_name!())
         .version(crate_version!())
         .help("clusters incoming lines")
@@ -73,57 +333,135 @@ fn main() {
                 .alias("j")
                 .arg(&ratio_arg)
                 .arg(&winkler_arg)
-                .arg(&file_arg)
-                .arg(&ors_arg)
-                .arg(&ofs_arg)
-                .arg(&irs_arg),
+                .args(&common_args),
         )
         .subcommand(
             SubCommand::with_name("levenshtein")
                 .alias("l")
                 .arg(&threshold_arg)
                 .arg(&damerau_arg)
-                .arg(&file_arg)
-                .arg(&ors_arg)
-                .arg(&ofs_arg)
-                .arg(&irs_arg),
+                .arg(&index_arg)
+                .args(&common_args),
         )
         .subcommand(
             SubCommand::with_name("normalized-levenshtein")
                 .alias("n")
                 .arg(&ratio_arg)
                 .arg(&damerau_arg)
-                .arg(&file_arg)
-                .arg(&ors_arg)
-                .arg(&ofs_arg)
-                .arg(&irs_arg),
+                .args(&common_args),
         )
         .subcommand(
             SubCommand::with_name("osa")
                 .alias("o")
                 .arg(&threshold_arg)
-                .arg(&file_arg)
-                .arg(&ors_arg)
-                .arg(&ofs_arg)
-                .arg(&irs_arg),
+                .arg(&index_arg)
+                .args(&common_args),
+        )
+        .subcommand(
+            SubCommand::with_name("dice")
+                .alias("d")
+                .arg(&ratio_arg)
+                .args(&common_args),
+        )
+        .subcommand(
+            SubCommand::with_name("hamming")
+                .alias("h")
+                .arg(&threshold_arg)
+                .arg(&unequal_arg)
+                .arg(&index_arg)
+                .args(&common_args),
+        )
+        .subcommand(
+            SubCommand::with_name("token-sort")
+                .alias("s")
+                .arg(&ratio_arg)
+                .args(&common_args),
+        )
+        .subcommand(
+            SubCommand::with_name("token-set")
+                .alias("t")
+                .arg(&ratio_arg)
+                .args(&common_args),
+        )
+        .subcommand(
+            SubCommand::with_name("jaccard")
+                .alias("q")
+                .arg(&ratio_arg)
+                .arg(&qgram_arg)
+                .args(&common_args),
+        )
+        .subcommand(
+            SubCommand::with_name("simhash")
+                .alias("f")
+                .arg(&bits_arg)
+                .args(&common_args),
+        )
+        .subcommand(
+            SubCommand::with_name("cosine")
+                .alias("c")
+                .arg(&ratio_arg)
+                .arg(&tokenize_arg)
+                .arg(&stopwords_arg)
+                .arg(&stopwords_file_arg)
+                .args(&common_args),
+        )
+        .subcommand(
+            SubCommand::with_name("phonetic")
+                .alias("p")
+                .arg(&encoding_arg)
+                .arg(&code_threshold_arg)
+                .args(&common_args),
         )
         .get_matches();
 
     match matches.subcommand() {
         ("jaro", Some(matches)) => {
-            cluster::Jaro::run(&matches);
+            run_cluster(matches, Jaro::from_matches(matches));
         }
 
         ("levenshtein", Some(matches)) => {
-            cluster::Levenshtein::run(&matches);
+            run_metric(matches, Levenshtein::from_matches(matches));
         }
 
         ("normalized-levenshtein", Some(matches)) => {
-            cluster::NormLevenshtein::run(&matches);
+            run_cluster(matches, NormLevenshtein::from_matches(matches));
         }
 
         ("osa", Some(matches)) => {
-            cluster::OSA::run(&matches);
+            run_metric(matches, OSA::from_matches(matches));
+        }
+
+        ("dice", Some(matches)) => {
+            run_cluster(matches, Dice::from_matches(matches));
+        }
+
+        ("hamming", Some(matches)) => {
+            run_metric(matches, Hamming::from_matches(matches));
+        }
+
+        ("token-sort", Some(matches)) => {
+            run_cluster(matches, TokenSort::from_matches(matches));
+        }
+
+        ("token-set", Some(matches)) => {
+            run_cluster(matches, TokenSet::from_matches(matches));
+        }
+
+        ("jaccard", Some(matches)) => {
+            run_cluster(matches, Jaccard::from_matches(matches));
+        }
+
+        ("simhash", Some(matches)) => {
+            run_cluster(matches, SimHash::from_matches(matches));
+        }
+
+        ("cosine", Some(matches)) => {
+            let algo = Cosine::from_matches(matches);
+            run_fitted(matches, |keys| algo.fit(keys));
+        }
+
+        ("phonetic", Some(matches)) => {
+            run_cluster(matches, Phonetic::from_matches(matches));
         }
 
         _ => println!("{}", matches.usage()),
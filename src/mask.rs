@@ -0,0 +1,289 @@
+//! Masking variable parts of records, such as the numbers and ids in log lines.
+
+use rayon::prelude::*;
+use regex::Regex;
+
+/// A kind of variable token replaced by a placeholder.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum MaskClass {
+    /// Hyphenated uuids, masked as `<UUID>`.
+    Uuid,
+    /// Iso dates and clock times, masked as `<DATE>`.
+    Date,
+    /// Ipv4 and ipv6 addresses with an optional port, masked as `<IP>`.
+    Ip,
+    /// `0x` prefixed or long mixed hex strings, masked as `<HEX>`.
+    Hex,
+    /// Integers and decimals, masked as `<NUM>`.
+    Number,
+}
+
+impl MaskClass {
+    fn rule(self) -> Rule {
+        let (pattern, placeholder, keep): (&str, &str, fn(&str) -> bool) = match self {
+            MaskClass::Uuid => (
+                r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+                "<UUID>",
+                any,
+            ),
+            MaskClass::Date => (
+                r"\b\d{4}-\d{2}-\d{2}(?:[T ]\d{2}:\d{2}(?::\d{2}(?:[.,]\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?)?|\b\d{2}:\d{2}:\d{2}(?:[.,]\d+)?",
+                "<DATE>",
+                any,
+            ),
+            MaskClass::Ip => (
+                r"\b\d{1,3}(?:\.\d{1,3}){3}(?::\d+)?\b|\b(?:[0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}\b|\b(?:[0-9a-fA-F]{1,4}:){1,6}(?::[0-9a-fA-F]{1,4}){1,6}\b",
+                "<IP>",
+                any,
+            ),
+            MaskClass::Hex => (
+                r"\b0[xX][0-9a-fA-F]+\b|\b[0-9a-fA-F]{6,}\b",
+                "<HEX>",
+                mixed_hex,
+            ),
+            MaskClass::Number => (r"\b\d+(?:\.\d+)?", "<NUM>", any),
+        };
+
+        Rule {
+            regex: Regex::new(pattern).unwrap(),
+            placeholder: placeholder.to_string(),
+            keep,
+        }
+    }
+}
+
+fn any(_: &str) -> bool {
+    true
+}
+
+fn mixed_hex(token: &str) -> bool {
+    token.starts_with("0x")
+        || token.starts_with("0X")
+        || (token.bytes().any(|b| b.is_ascii_digit())
+            && token.bytes().any(|b| b.is_ascii_alphabetic()))
+}
+
+struct Rule {
+    regex: Regex,
+    placeholder: String,
+    keep: fn(&str) -> bool,
+}
+
+impl Rule {
+    fn apply(&self, record: &str) -> String {
+        self.regex
+            .replace_all(record, |caps: &regex::Captures| {
+                let token = &caps[0];
+                if (self.keep)(token) {
+                    self.placeholder.clone()
+                } else {
+                    token.to_string()
+                }
+            })
+            .into_owned()
+    }
+}
+
+/// Replaces configured classes of variable tokens with placeholders.
+///
+/// Custom patterns are applied first in the order added, then classes from the
+/// most to the least specific, so an ip is never masked as four numbers.
+#[derive(Default)]
+pub struct Masker {
+    patterns: Vec<Rule>,
+    classes: Vec<MaskClass>,
+    rules: Vec<Rule>,
+}
+
+impl Masker {
+    /// Masks nothing until classes or patterns are added.
+    pub fn new() -> Masker {
+        Masker::default()
+    }
+
+    /// Masks every built in class.
+    pub fn all() -> Masker {
+        [
+            MaskClass::Uuid,
+            MaskClass::Date,
+            MaskClass::Ip,
+            MaskClass::Hex,
+            MaskClass::Number,
+        ]
+        .iter()
+        .fold(Masker::new(), |masker, &class| masker.class(class))
+    }
+
+    /// Masks tokens of `class`.
+    pub fn class(mut self, class: MaskClass) -> Masker {
+        if !self.classes.contains(&class) {
+            self.classes.push(class);
+            self.classes.sort();
+            self.rules = self.classes.iter().map(|class| class.rule()).collect();
+        }
+
+        self
+    }
+
+    /// Masks matches of `pattern` with `placeholder`.
+    pub fn pattern(mut self, pattern: &str, placeholder: &str) -> Result<Masker, regex::Error> {
+        self.patterns.push(Rule {
+            regex: Regex::new(pattern)?,
+            placeholder: placeholder.to_string(),
+            keep: any,
+        });
+
+        Ok(self)
+    }
+
+    /// `record` with every configured token replaced.
+    pub fn mask(&self, record: &str) -> String {
+        self.patterns
+            .iter()
+            .chain(self.rules.iter())
+            .fold(record.to_string(), |record, rule| rule.apply(&record))
+    }
+
+    /// [`Masker::mask`] over a batch of records, in parallel.
+    pub fn masks(&self, records: &[String]) -> Vec<String> {
+        records.par_iter().map(|record| self.mask(record)).collect()
+    }
+
+    /// The placeholders masked records may contain, custom patterns first.
+    pub fn placeholders(&self) -> Vec<&str> {
+        self.patterns
+            .iter()
+            .chain(self.rules.iter())
+            .map(|rule| rule.placeholder.as_str())
+            .collect()
+    }
+}
+
+/// The placeholder for tokens that vary between members of a template.
+pub const WILDCARD: &str = "<*>";
+
+/// Derives a template from whitespace separated `records`.
+///
+/// Tokens shared by every record in sequence are kept and each run of tokens
+/// that differ is replaced by a single [`WILDCARD`].
+pub fn template<'a, I>(records: I) -> String
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut records = records.into_iter();
+    let mut tokens = match records.next() {
+        Some(first) => first.split_whitespace().map(Some).collect::<Vec<_>>(),
+        None => return String::new(),
+    };
+
+    for record in records {
+        tokens = align(&tokens, &record.split_whitespace().collect::<Vec<&str>>());
+    }
+
+    tokens
+        .iter()
+        .map(|token| token.unwrap_or(WILDCARD))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Keeps the longest common subsequence of `template` and `tokens`, with
+/// `None` standing in for each gap between kept tokens.
+fn align<'a>(template: &[Option<&'a str>], tokens: &[&'a str]) -> Vec<Option<&'a str>> {
+    let (n, m) = (template.len(), tokens.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if template[i] == Some(tokens[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut aligned = Vec::new();
+    let mut gap = false;
+    let (mut i, mut j) = (0, 0);
+
+    while i < n || j < m {
+        if i < n && j < m && template[i] == Some(tokens[j]) {
+            if gap {
+                aligned.push(None);
+                gap = false;
+            }
+
+            aligned.push(template[i]);
+            i += 1;
+            j += 1;
+        } else {
+            gap = true;
+            if j >= m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+
+    if gap {
+        aligned.push(None);
+    }
+
+    aligned
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn masks_classes() {
+        let masker = Masker::all();
+
+        assert_eq!(
+            masker.mask("connection from 10.0.0.12:443 closed after 250ms"),
+            "connection from <IP> closed after <NUM>ms"
+        );
+        assert_eq!(
+            masker.mask("2019-10-01T12:30:01Z req 123e4567-e89b-12d3-a456-426655440000 at 0x7ffe"),
+            "<DATE> req <UUID> at <HEX>"
+        );
+        assert_eq!(
+            masker.mask("commit 9fceb02d0ae5 by dead beef, 12 files"),
+            "commit <HEX> by dead beef, <NUM> files"
+        );
+        assert_eq!(
+            masker.mask("std::io error on fe80::1ff:fe23 port 8080"),
+            "std::io error on <IP> port <NUM>"
+        );
+    }
+
+    #[test]
+    fn masks_only_configured() {
+        let masker = Masker::new()
+            .class(MaskClass::Number)
+            .pattern(r"user \w+", "user <USER>")
+            .unwrap();
+
+        assert_eq!(
+            masker.mask("user bob logged in from 10.0.0.1 after 3 tries"),
+            "user <USER> logged in from <NUM>.<NUM> after <NUM> tries"
+        );
+    }
+
+    #[test]
+    fn templates_wildcard_differences() {
+        let records = [
+            "connection from <IP> closed after <NUM>ms",
+            "connection from <IP> closed after <NUM>ms by peer",
+            "connection from <IP> reset after <NUM>ms",
+        ];
+
+        assert_eq!(
+            template(records.iter().cloned()),
+            "connection from <IP> <*> after <NUM>ms <*>"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Normalizing records so that superficial differences do not affect similarity.
+
+use rayon::prelude::*;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// A unicode normalization form.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Form {
+    /// Canonical composition.
+    Nfc,
+    /// Compatibility composition, folding ligatures, widths and the like.
+    Nfkc,
+}
+
+/// A configurable series of normalization steps.
+///
+/// Steps run in a fixed order: unicode normalization, diacritic stripping,
+/// case folding, punctuation removal, whitespace collapsing and trimming.
+#[derive(Default)]
+pub struct Normalizer {
+    kept: Vec<String>,
+    form: Option<Form>,
+    diacritics: bool,
+    lowercase: bool,
+    punctuation: bool,
+    whitespace: bool,
+    trim: bool,
+}
+
+impl Normalizer {
+    /// Every step disabled, so output equals input until steps are enabled.
+    pub fn new() -> Normalizer {
+        Normalizer::default()
+    }
+
+    /// Every step, using compatibility composition.
+    pub fn all() -> Normalizer {
+        Normalizer::new()
+            .form(Form::Nfkc)
+            .diacritics(true)
+            .lowercase(true)
+            .punctuation(true)
+            .whitespace(true)
+            .trim(true)
+    }
+
+    /// Normalize to unicode `form`.
+    pub fn form(mut self, form: Form) -> Normalizer {
+        self.form = Some(form);
+        self
+    }
+
+    /// Strip combining marks such as accents.
+    pub fn diacritics(mut self, diacritics: bool) -> Normalizer {
+        self.diacritics = diacritics;
+        self
+    }
+
+    /// Fold to lowercase.
+    pub fn lowercase(mut self, lowercase: bool) -> Normalizer {
+        self.lowercase = lowercase;
+        self
+    }
+
+    /// Remove characters that are neither alphanumeric nor whitespace.
+    pub fn punctuation(mut self, punctuation: bool) -> Normalizer {
+        self.punctuation = punctuation;
+        self
+    }
+
+    /// Replace runs of whitespace with a single space.
+    pub fn whitespace(mut self, whitespace: bool) -> Normalizer {
+        self.whitespace = whitespace;
+        self
+    }
+
+    /// Remove leading and trailing whitespace.
+    pub fn trim(mut self, trim: bool) -> Normalizer {
+        self.trim = trim;
+        self
+    }
+
+    /// Leave occurrences of `kept`, such as mask placeholders, as they are.
+    ///
+    /// The other steps still apply to the text around them.
+    pub fn keep(mut self, kept: &[&str]) -> Normalizer {
+        for &kept in kept {
+            if !kept.is_empty() && !self.kept.iter().any(|k| k == kept) {
+                self.kept.push(kept.to_string());
+            }
+        }
+
+        self
+    }
+
+    /// `record` with every configured step applied.
+    pub fn normalize(&self, record: &str) -> String {
+        let mut normalized = String::with_capacity(record.len());
+        let mut rest = record;
+
+        // the earliest kept occurrence, preferring the longest where several start together
+        while let Some((start, kept)) = self
+            .kept
+            .iter()
+            .filter_map(|kept| rest.find(kept.as_str()).map(|start| (start, kept)))
+            .min_by_key(|&(start, kept)| (start, std::cmp::Reverse(kept.len())))
+        {
+            normalized.push_str(&self.characters(&rest[..start]));
+            normalized.push_str(kept);
+            rest = &rest[start + kept.len()..];
+        }
+        normalized.push_str(&self.characters(rest));
+
+        if self.whitespace {
+            normalized = collapse_whitespace(&normalized);
+        }
+
+        if self.trim {
+            normalized = normalized.trim().to_string();
+        }
+
+        normalized
+    }
+
+    fn characters(&self, record: &str) -> String {
+        let mut record = match self.form {
+            Some(Form::Nfc) => record.nfc().collect(),
+            Some(Form::Nfkc) => record.nfkc().collect(),
+            None => record.to_string(),
+        };
+
+        if self.diacritics {
+            record = record
+                .nfd()
+                .filter(|&c| !is_combining_mark(c))
+                .nfc()
+                .collect();
+        }
+
+        if self.lowercase {
+            record = record.to_lowercase();
+        }
+
+        if self.punctuation {
+            record.retain(|c| c.is_alphanumeric() || c.is_whitespace());
+        }
+
+        record
+    }
+
+    /// The normalized key of every record, ready for comparison.
+    pub fn normalize_all(&self, records: &[String]) -> Vec<String> {
+        records
+            .par_iter()
+            .map(|record| self.normalize(record))
+            .collect()
+    }
+}
+
+fn collapse_whitespace(record: &str) -> String {
+    let mut collapsed = String::with_capacity(record.len());
+    let mut space = false;
+
+    for c in record.chars() {
+        if c.is_whitespace() {
+            if !space {
+                collapsed.push(' ');
+            }
+            space = true;
+        } else {
+            collapsed.push(c);
+            space = false;
+        }
+    }
+
+    collapsed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn untouched_by_default() {
+        assert_eq!(Normalizer::new().normalize(" Foo,  Bar "), " Foo,  Bar ");
+    }
+
+    #[test]
+    fn every_step() {
+        let normalizer = Normalizer::all();
+
+        assert_eq!(normalizer.normalize("  Foo \t Bar!  "), "foo bar");
+        assert_eq!(normalizer.normalize("Crème  Brûlée"), "creme brulee");
+        assert_eq!(normalizer.normalize("ﬁne Ｗｉｄｅ"), "fine wide");
+    }
+
+    #[test]
+    fn composition_forms() {
+        let decomposed = "e\u{301}";
+
+        assert_eq!(Normalizer::new().form(Form::Nfc).normalize(decomposed), "é");
+        assert_eq!(Normalizer::new().form(Form::Nfc).normalize("ﬁ"), "ﬁ");
+        assert_eq!(Normalizer::new().form(Form::Nfkc).normalize("ﬁ"), "fi");
+    }
+
+    #[test]
+    fn kept_tokens() {
+        let normalizer = Normalizer::all().keep(&["<IP>", "<NUM>"]);
+
+        assert_eq!(
+            normalizer.normalize(" Closed <IP>,  after <NUM>ms! "),
+            "closed <IP> after <NUM>ms"
+        );
+        assert_eq!(normalizer.normalize("<IP><NUM>"), "<IP><NUM>");
+    }
+}
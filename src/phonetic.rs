@@ -0,0 +1,905 @@
+//! Phonetic codes, so that names spelled differently but pronounced alike compare equal.
+
+/// A phonetic encoding of words.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Encoding {
+    /// American soundex: a letter followed by three digits.
+    Soundex,
+    /// Double metaphone: a primary and an alternate code of up to four characters.
+    Metaphone,
+    /// New york state identification and intelligence system codes.
+    Nysiis,
+}
+
+impl Encoding {
+    /// Codes for `record`, encoding each run of letters and joining them with spaces.
+    ///
+    /// Double metaphone yields one code for every way of choosing the primary
+    /// or alternate code of each word, the others a single code. Words that
+    /// encode to nothing are left out, so a record without letters has no codes.
+    pub fn codes(self, record: &str) -> Vec<String> {
+        let words = record
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|word| !word.is_empty())
+            .map(|word| match self {
+                Encoding::Soundex => vec![soundex(word)],
+                Encoding::Nysiis => vec![nysiis(word)],
+                Encoding::Metaphone => {
+                    let (primary, alternate) = double_metaphone(word);
+                    if primary == alternate {
+                        vec![primary]
+                    } else {
+                        vec![primary, alternate]
+                    }
+                }
+            });
+
+        let mut codes = Vec::new();
+        for word in words {
+            let word = word
+                .into_iter()
+                .filter(|code| !code.is_empty())
+                .collect::<Vec<String>>();
+
+            if word.is_empty() {
+                continue;
+            } else if codes.is_empty() {
+                codes = word;
+            } else {
+                codes = codes
+                    .iter()
+                    .flat_map(|code| word.iter().map(move |next| format!("{} {}", code, next)))
+                    .collect();
+            }
+        }
+
+        codes
+    }
+}
+
+fn letters(word: &str) -> Vec<char> {
+    word.chars()
+        .flat_map(char::to_uppercase)
+        .filter(|c| c.is_ascii_alphabetic())
+        .collect()
+}
+
+/// The american soundex code of `word`, ignoring anything but ascii letters.
+pub fn soundex(word: &str) -> String {
+    fn digit(c: char) -> Option<char> {
+        match c {
+            'B' | 'F' | 'P' | 'V' => Some('1'),
+            'C' | 'G' | 'J' | 'K' | 'Q' | 'S' | 'X' | 'Z' => Some('2'),
+            'D' | 'T' => Some('3'),
+            'L' => Some('4'),
+            'M' | 'N' => Some('5'),
+            'R' => Some('6'),
+            _ => None,
+        }
+    }
+
+    let letters = letters(word);
+    let first = match letters.first() {
+        Some(&first) => first,
+        None => return String::new(),
+    };
+
+    let mut code = first.to_string();
+    let mut last = digit(first);
+    for &c in &letters[1..] {
+        match digit(c) {
+            Some(d) => {
+                if last != Some(d) {
+                    code.push(d);
+                }
+                last = Some(d);
+            }
+            // H and W do not separate letters with the same code, vowels do.
+            None if c == 'H' || c == 'W' => (),
+            None => last = None,
+        }
+
+        if code.len() == 4 {
+            break;
+        }
+    }
+
+    while code.len() < 4 {
+        code.push('0');
+    }
+
+    code
+}
+
+/// The nysiis code of `word`, ignoring anything but ascii letters and without truncation.
+pub fn nysiis(word: &str) -> String {
+    fn vowel(c: char) -> bool {
+        "AEIOU".contains(c)
+    }
+
+    let mut name = letters(word).into_iter().collect::<String>();
+    if name.is_empty() {
+        return name;
+    }
+
+    for &(prefix, with) in &[
+        ("MAC", "MCC"),
+        ("KN", "N"),
+        ("K", "C"),
+        ("PH", "FF"),
+        ("PF", "FF"),
+        ("SCH", "SSS"),
+    ] {
+        if name.starts_with(prefix) {
+            name = format!("{}{}", with, &name[prefix.len()..]);
+            break;
+        }
+    }
+
+    for &(suffix, with) in &[
+        ("EE", "Y"),
+        ("IE", "Y"),
+        ("DT", "D"),
+        ("RT", "D"),
+        ("RD", "D"),
+        ("NT", "D"),
+        ("ND", "D"),
+    ] {
+        if name.ends_with(suffix) {
+            name = format!("{}{}", &name[..name.len() - suffix.len()], with);
+            break;
+        }
+    }
+
+    let name = name.chars().collect::<Vec<char>>();
+    let at = |i: usize| name.get(i).cloned().unwrap_or('\0');
+
+    // `H` and `W` copy the previous position as already translated, so `prev`
+    // follows the translation rather than the input.
+    let mut key = name[0].to_string();
+    let mut prev = name[0];
+    let mut i = 1;
+    while i < name.len() {
+        let (c, next) = (name[i], at(i + 1));
+        let sound = match c {
+            'E' if next == 'V' => {
+                i += 1;
+                "AF".to_string()
+            }
+            'A' | 'E' | 'I' | 'O' | 'U' => "A".to_string(),
+            'Q' => "G".to_string(),
+            'Z' => "S".to_string(),
+            'M' => "N".to_string(),
+            'K' if next == 'N' => "N".to_string(),
+            'K' => "C".to_string(),
+            'S' if next == 'C' && at(i + 2) == 'H' => {
+                i += 2;
+                "SS".to_string()
+            }
+            'P' if next == 'H' => {
+                i += 1;
+                "F".to_string()
+            }
+            'H' if !vowel(prev) || !vowel(next) => prev.to_string(),
+            'W' if vowel(prev) => prev.to_string(),
+            c => c.to_string(),
+        };
+
+        prev = sound.chars().last().unwrap();
+        if !key.ends_with(sound.chars().last().unwrap()) {
+            key.push_str(&sound);
+        }
+        i += 1;
+    }
+
+    if key.len() > 1 && key.ends_with('S') {
+        key.pop();
+    }
+
+    if key.ends_with("AY") {
+        key.truncate(key.len() - 2);
+        key.push('Y');
+    }
+
+    if key.len() > 1 && key.ends_with('A') {
+        key.pop();
+    }
+
+    key
+}
+
+/// The primary and alternate double metaphone codes of `word`.
+pub fn double_metaphone(word: &str) -> (String, String) {
+    let value = word
+        .chars()
+        .flat_map(char::to_uppercase)
+        .filter(|c| c.is_alphabetic())
+        .collect::<Vec<char>>();
+
+    let mut metaphone = Metaphone {
+        value,
+        primary: String::new(),
+        alternate: String::new(),
+    };
+    metaphone.encode();
+
+    (metaphone.primary, metaphone.alternate)
+}
+
+const METAPHONE_LENGTH: usize = 4;
+
+struct Metaphone {
+    value: Vec<char>,
+    primary: String,
+    alternate: String,
+}
+
+impl Metaphone {
+    fn at(&self, index: isize) -> char {
+        if index < 0 {
+            return '\0';
+        }
+
+        self.value.get(index as usize).cloned().unwrap_or('\0')
+    }
+
+    fn len(&self) -> isize {
+        self.value.len() as isize
+    }
+
+    /// Whether the `len` characters from `start` are one of `options`.
+    fn contains(&self, start: isize, len: isize, options: &[&str]) -> bool {
+        if start < 0 || start + len > self.len() {
+            return false;
+        }
+
+        let target = self.value[start as usize..(start + len) as usize]
+            .iter()
+            .collect::<String>();
+
+        options.contains(&target.as_str())
+    }
+
+    fn vowel(&self, index: isize) -> bool {
+        "AEIOUY".contains(self.at(index))
+    }
+
+    fn slavo_germanic(&self) -> bool {
+        let value = self.value.iter().collect::<String>();
+        ["W", "K", "CZ", "WITZ"]
+            .iter()
+            .any(|pattern| value.contains(pattern))
+    }
+
+    fn both(&mut self, code: &str) {
+        self.add(code, code);
+    }
+
+    fn add(&mut self, primary: &str, alternate: &str) {
+        self.primary.push_str(primary);
+        self.alternate.push_str(alternate);
+    }
+
+    fn encode(&mut self) {
+        let slavo_germanic = self.slavo_germanic();
+        let mut index = 0;
+
+        if self.contains(0, 2, &["GN", "KN", "PN", "WR", "PS"]) {
+            index = 1;
+        }
+
+        while index < self.len()
+            && (self.primary.len() < METAPHONE_LENGTH || self.alternate.len() < METAPHONE_LENGTH)
+        {
+            index = match self.at(index) {
+                'A' | 'E' | 'I' | 'O' | 'U' | 'Y' => {
+                    if index == 0 {
+                        self.both("A");
+                    }
+                    index + 1
+                }
+                'B' => {
+                    self.both("P");
+                    self.skip(index, 'B')
+                }
+                'Ç' => {
+                    self.both("S");
+                    index + 1
+                }
+                'C' => self.c(index),
+                'D' => self.d(index),
+                'F' => {
+                    self.both("F");
+                    self.skip(index, 'F')
+                }
+                'G' => self.g(index, slavo_germanic),
+                'H' => self.h(index),
+                'J' => self.j(index, slavo_germanic),
+                'K' => {
+                    self.both("K");
+                    self.skip(index, 'K')
+                }
+                'L' => self.l(index),
+                'M' => {
+                    self.both("M");
+                    if self.at(index + 1) == 'M'
+                        || (self.contains(index - 1, 3, &["UMB"])
+                            && (index + 1 == self.len() - 1
+                                || self.contains(index + 2, 2, &["ER"])))
+                    {
+                        index + 2
+                    } else {
+                        index + 1
+                    }
+                }
+                'N' => {
+                    self.both("N");
+                    self.skip(index, 'N')
+                }
+                'Ñ' => {
+                    self.both("N");
+                    index + 1
+                }
+                'P' => self.p(index),
+                'Q' => {
+                    self.both("K");
+                    self.skip(index, 'Q')
+                }
+                'R' => self.r(index, slavo_germanic),
+                'S' => self.s(index, slavo_germanic),
+                'T' => self.t(index),
+                'V' => {
+                    self.both("F");
+                    self.skip(index, 'V')
+                }
+                'W' => self.w(index),
+                'X' => self.x(index),
+                'Z' => self.z(index, slavo_germanic),
+                _ => index + 1,
+            };
+        }
+
+        self.primary.truncate(METAPHONE_LENGTH);
+        self.alternate.truncate(METAPHONE_LENGTH);
+    }
+
+    fn skip(&self, index: isize, double: char) -> isize {
+        if self.at(index + 1) == double {
+            index + 2
+        } else {
+            index + 1
+        }
+    }
+
+    fn c(&mut self, index: isize) -> isize {
+        if self.c_as_k(index) {
+            self.both("K");
+            index + 2
+        } else if index == 0 && self.contains(index, 6, &["CAESAR"]) {
+            self.both("S");
+            index + 2
+        } else if self.contains(index, 2, &["CH"]) {
+            self.ch(index)
+        } else if self.contains(index, 2, &["CZ"]) && !self.contains(index - 2, 4, &["WICZ"]) {
+            self.add("S", "X");
+            index + 2
+        } else if self.contains(index + 1, 3, &["CIA"]) {
+            self.both("X");
+            index + 3
+        } else if self.contains(index, 2, &["CC"]) && !(index == 1 && self.at(0) == 'M') {
+            self.cc(index)
+        } else if self.contains(index, 2, &["CK", "CG", "CQ"]) {
+            self.both("K");
+            index + 2
+        } else if self.contains(index, 2, &["CI", "CE", "CY"]) {
+            if self.contains(index, 3, &["CIO", "CIE", "CIA"]) {
+                self.add("S", "X");
+            } else {
+                self.both("S");
+            }
+            index + 2
+        } else {
+            self.both("K");
+            if self.contains(index + 1, 2, &[" C", " Q", " G"]) {
+                index + 3
+            } else if self.contains(index + 1, 1, &["C", "K", "Q"])
+                && !self.contains(index + 1, 2, &["CE", "CI"])
+            {
+                index + 2
+            } else {
+                index + 1
+            }
+        }
+    }
+
+    fn c_as_k(&self, index: isize) -> bool {
+        if self.contains(index, 4, &["CHIA"]) {
+            true
+        } else if index <= 1 || self.vowel(index - 2) || !self.contains(index - 1, 3, &["ACH"]) {
+            false
+        } else {
+            let c = self.at(index + 2);
+            (c != 'I' && c != 'E') || self.contains(index - 2, 6, &["BACHER", "MACHER"])
+        }
+    }
+
+    fn cc(&mut self, index: isize) -> isize {
+        if self.contains(index + 2, 1, &["I", "E", "H"]) && !self.contains(index + 2, 2, &["HU"]) {
+            if (index == 1 && self.at(index - 1) == 'A')
+                || self.contains(index - 1, 5, &["UCCEE", "UCCES"])
+            {
+                self.both("KS");
+            } else {
+                self.both("X");
+            }
+            index + 3
+        } else {
+            self.both("K");
+            index + 2
+        }
+    }
+
+    fn ch(&mut self, index: isize) -> isize {
+        if index > 0 && self.contains(index, 4, &["CHAE"]) {
+            self.add("K", "X");
+        } else if self.ch_greek(index) || self.ch_germanic(index) {
+            self.both("K");
+        } else if index > 0 {
+            if self.contains(0, 2, &["MC"]) {
+                self.both("K");
+            } else {
+                self.add("X", "K");
+            }
+        } else {
+            self.both("X");
+        }
+
+        index + 2
+    }
+
+    fn ch_greek(&self, index: isize) -> bool {
+        index == 0
+            && (self.contains(index + 1, 5, &["HARAC", "HARIS"])
+                || self.contains(index + 1, 3, &["HOR", "HYM", "HIA", "HEM"]))
+            && !self.contains(0, 5, &["CHORE"])
+    }
+
+    fn ch_germanic(&self, index: isize) -> bool {
+        self.contains(0, 4, &["VAN ", "VON "])
+            || self.contains(0, 3, &["SCH"])
+            || self.contains(index - 2, 6, &["ORCHES", "ARCHIT", "ORCHID"])
+            || self.contains(index + 2, 1, &["T", "S"])
+            || ((self.contains(index - 1, 1, &["A", "O", "U", "E"]) || index == 0)
+                && (self.contains(
+                    index + 2,
+                    1,
+                    &["L", "R", "N", "M", "B", "H", "F", "V", "W", " "],
+                ) || index + 1 == self.len() - 1))
+    }
+
+    fn d(&mut self, index: isize) -> isize {
+        if self.contains(index, 2, &["DG"]) {
+            if self.contains(index + 2, 1, &["I", "E", "Y"]) {
+                self.both("J");
+                index + 3
+            } else {
+                self.both("TK");
+                index + 2
+            }
+        } else if self.contains(index, 2, &["DT", "DD"]) {
+            self.both("T");
+            index + 2
+        } else {
+            self.both("T");
+            index + 1
+        }
+    }
+
+    fn g(&mut self, index: isize, slavo_germanic: bool) -> isize {
+        if self.at(index + 1) == 'H' {
+            self.gh(index)
+        } else if self.at(index + 1) == 'N' {
+            if index == 1 && self.vowel(0) && !slavo_germanic {
+                self.add("KN", "N");
+            } else if !self.contains(index + 2, 2, &["EY"])
+                && self.at(index + 1) != 'Y'
+                && !slavo_germanic
+            {
+                self.add("N", "KN");
+            } else {
+                self.both("KN");
+            }
+            index + 2
+        } else if self.contains(index + 1, 2, &["LI"]) && !slavo_germanic {
+            self.add("KL", "L");
+            index + 2
+        } else if self.g_ambiguous(index) {
+            self.add("K", "J");
+            index + 2
+        } else if self.contains(index + 1, 1, &["E", "I", "Y"])
+            || self.contains(index - 1, 4, &["AGGI", "OGGI"])
+        {
+            if self.contains(0, 4, &["VAN ", "VON "])
+                || self.contains(0, 3, &["SCH"])
+                || self.contains(index + 1, 2, &["ET"])
+            {
+                self.both("K");
+            } else if self.contains(index + 1, 3, &["IER"]) {
+                self.both("J");
+            } else {
+                self.add("J", "K");
+            }
+            index + 2
+        } else if self.at(index + 1) == 'G' {
+            self.both("K");
+            index + 2
+        } else {
+            self.both("K");
+            index + 1
+        }
+    }
+
+    /// Leading -ges-, -gep-, -gel-, -gie- and the like, or -ger- and -gy- elsewhere.
+    fn g_ambiguous(&self, index: isize) -> bool {
+        let leading = index == 0
+            && (self.at(index + 1) == 'Y'
+                || self.contains(
+                    index + 1,
+                    2,
+                    &[
+                        "ES", "EP", "EB", "EL", "EY", "IB", "IL", "IN", "IE", "EI", "ER",
+                    ],
+                ));
+
+        leading
+            || ((self.contains(index + 1, 2, &["ER"]) || self.at(index + 1) == 'Y')
+                && !self.contains(0, 6, &["DANGER", "RANGER", "MANGER"])
+                && !self.contains(index - 1, 1, &["E", "I"])
+                && !self.contains(index - 1, 3, &["RGY", "OGY"]))
+    }
+
+    fn gh(&mut self, index: isize) -> isize {
+        if index > 0 && !self.vowel(index - 1) {
+            self.both("K");
+        } else if index == 0 {
+            if self.at(index + 2) == 'I' {
+                self.both("J");
+            } else {
+                self.both("K");
+            }
+        } else if (index > 1 && self.contains(index - 2, 1, &["B", "H", "D"]))
+            || (index > 2 && self.contains(index - 3, 1, &["B", "H", "D"]))
+            || (index > 3 && self.contains(index - 4, 1, &["B", "H"]))
+        {
+            // Silent, as in "hugh".
+        } else if index > 2
+            && self.at(index - 1) == 'U'
+            && self.contains(index - 3, 1, &["C", "G", "L", "R", "T"])
+        {
+            self.both("F");
+        } else if index > 0 && self.at(index - 1) != 'I' {
+            self.both("K");
+        }
+
+        index + 2
+    }
+
+    fn h(&mut self, index: isize) -> isize {
+        if (index == 0 || self.vowel(index - 1)) && self.vowel(index + 1) {
+            self.both("H");
+            index + 2
+        } else {
+            index + 1
+        }
+    }
+
+    fn j(&mut self, index: isize, slavo_germanic: bool) -> isize {
+        if self.contains(index, 4, &["JOSE"]) || self.contains(0, 4, &["SAN "]) {
+            if (index == 0 && self.at(index + 4) == ' ')
+                || self.len() == 4
+                || self.contains(0, 4, &["SAN "])
+            {
+                self.both("H");
+            } else {
+                self.add("J", "H");
+            }
+            return index + 1;
+        }
+
+        if index == 0 {
+            self.add("J", "A");
+        } else if self.vowel(index - 1)
+            && !slavo_germanic
+            && (self.at(index + 1) == 'A' || self.at(index + 1) == 'O')
+        {
+            self.add("J", "H");
+        } else if index == self.len() - 1 {
+            self.add("J", "");
+        } else if !self.contains(index + 1, 1, &["L", "T", "K", "S", "N", "M", "B", "Z"])
+            && !self.contains(index - 1, 1, &["S", "K", "L"])
+        {
+            self.both("J");
+        }
+
+        self.skip(index, 'J')
+    }
+
+    fn l(&mut self, index: isize) -> isize {
+        if self.at(index + 1) != 'L' {
+            self.both("L");
+            return index + 1;
+        }
+
+        let len = self.len();
+        let spanish = (index == len - 3 && self.contains(index - 1, 4, &["ILLO", "ILLA", "ALLE"]))
+            || ((self.contains(len - 2, 2, &["AS", "OS"])
+                || self.contains(len - 1, 1, &["A", "O"]))
+                && self.contains(index - 1, 4, &["ALLE"]));
+
+        if spanish {
+            self.add("L", "");
+        } else {
+            self.both("L");
+        }
+
+        index + 2
+    }
+
+    fn p(&mut self, index: isize) -> isize {
+        if self.at(index + 1) == 'H' {
+            self.both("F");
+            index + 2
+        } else {
+            self.both("P");
+            if self.contains(index + 1, 1, &["P", "B"]) {
+                index + 2
+            } else {
+                index + 1
+            }
+        }
+    }
+
+    fn r(&mut self, index: isize, slavo_germanic: bool) -> isize {
+        if index == self.len() - 1
+            && !slavo_germanic
+            && self.contains(index - 2, 2, &["IE"])
+            && !self.contains(index - 4, 2, &["ME", "MA"])
+        {
+            self.add("", "R");
+        } else {
+            self.both("R");
+        }
+
+        self.skip(index, 'R')
+    }
+
+    fn s(&mut self, index: isize, slavo_germanic: bool) -> isize {
+        if self.contains(index - 1, 3, &["ISL", "YSL"]) {
+            index + 1
+        } else if index == 0 && self.contains(index, 5, &["SUGAR"]) {
+            self.add("X", "S");
+            index + 1
+        } else if self.contains(index, 2, &["SH"]) {
+            if self.contains(index + 1, 4, &["HEIM", "HOEK", "HOLM", "HOLZ"]) {
+                self.both("S");
+            } else {
+                self.both("X");
+            }
+            index + 2
+        } else if self.contains(index, 3, &["SIO", "SIA"]) || self.contains(index, 4, &["SIAN"]) {
+            if slavo_germanic {
+                self.both("S");
+            } else {
+                self.add("S", "X");
+            }
+            index + 3
+        } else if (index == 0 && self.contains(index + 1, 1, &["M", "N", "L", "W"]))
+            || self.contains(index + 1, 1, &["Z"])
+        {
+            self.add("S", "X");
+            self.skip(index, 'Z')
+        } else if self.contains(index, 2, &["SC"]) {
+            self.sc(index)
+        } else {
+            if index == self.len() - 1 && self.contains(index - 2, 2, &["AI", "OI"]) {
+                self.add("", "S");
+            } else {
+                self.both("S");
+            }
+
+            if self.contains(index + 1, 1, &["S", "Z"]) {
+                index + 2
+            } else {
+                index + 1
+            }
+        }
+    }
+
+    fn sc(&mut self, index: isize) -> isize {
+        if self.at(index + 2) == 'H' {
+            if self.contains(index + 3, 2, &["OO", "ER", "EN", "UY", "ED", "EM"]) {
+                if self.contains(index + 3, 2, &["ER", "EN"]) {
+                    self.add("X", "SK");
+                } else {
+                    self.both("SK");
+                }
+            } else if index == 0 && !self.vowel(3) && self.at(3) != 'W' {
+                self.add("X", "S");
+            } else {
+                self.both("X");
+            }
+        } else if self.contains(index + 2, 1, &["I", "E", "Y"]) {
+            self.both("S");
+        } else {
+            self.both("SK");
+        }
+
+        index + 3
+    }
+
+    fn t(&mut self, index: isize) -> isize {
+        if self.contains(index, 4, &["TION"]) || self.contains(index, 3, &["TIA", "TCH"]) {
+            self.both("X");
+            index + 3
+        } else if self.contains(index, 2, &["TH"]) || self.contains(index, 3, &["TTH"]) {
+            if self.contains(index + 2, 2, &["OM", "AM"])
+                || self.contains(0, 4, &["VAN ", "VON "])
+                || self.contains(0, 3, &["SCH"])
+            {
+                self.both("T");
+            } else {
+                self.add("0", "T");
+            }
+            index + 2
+        } else {
+            self.both("T");
+            if self.contains(index + 1, 1, &["T", "D"]) {
+                index + 2
+            } else {
+                index + 1
+            }
+        }
+    }
+
+    fn w(&mut self, index: isize) -> isize {
+        if self.contains(index, 2, &["WR"]) {
+            self.both("R");
+            index + 2
+        } else if index == 0 && (self.vowel(index + 1) || self.contains(index, 2, &["WH"])) {
+            if self.vowel(index + 1) {
+                self.add("A", "F");
+            } else {
+                self.both("A");
+            }
+            index + 1
+        } else if (index == self.len() - 1 && self.vowel(index - 1))
+            || self.contains(index - 1, 5, &["EWSKI", "EWSKY", "OWSKI", "OWSKY"])
+            || self.contains(0, 3, &["SCH"])
+        {
+            self.add("", "F");
+            index + 1
+        } else if self.contains(index, 4, &["WICZ", "WITZ"]) {
+            self.add("TS", "FX");
+            index + 4
+        } else {
+            index + 1
+        }
+    }
+
+    fn x(&mut self, index: isize) -> isize {
+        if index == 0 {
+            self.both("S");
+            return index + 1;
+        }
+
+        let french = index == self.len() - 1
+            && (self.contains(index - 3, 3, &["IAU", "EAU"])
+                || self.contains(index - 2, 2, &["AU", "OU"]));
+        if !french {
+            self.both("KS");
+        }
+
+        if self.contains(index + 1, 1, &["C", "X"]) {
+            index + 2
+        } else {
+            index + 1
+        }
+    }
+
+    fn z(&mut self, index: isize, slavo_germanic: bool) -> isize {
+        if self.at(index + 1) == 'H' {
+            self.both("J");
+            return index + 2;
+        }
+
+        if self.contains(index + 1, 2, &["ZO", "ZI", "ZA"])
+            || (slavo_germanic && index > 0 && self.at(index - 1) != 'T')
+        {
+            self.add("S", "TS");
+        } else {
+            self.both("S");
+        }
+
+        self.skip(index, 'Z')
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn soundex_codes() {
+        for &(word, code) in &[
+            ("Robert", "R163"),
+            ("Rupert", "R163"),
+            ("Ashcraft", "A261"),
+            ("Tymczak", "T522"),
+            ("Pfister", "P236"),
+            ("Lee", "L000"),
+            ("", ""),
+        ] {
+            assert_eq!(soundex(word), code, "{}", word);
+        }
+    }
+
+    #[test]
+    fn nysiis_codes() {
+        for &(word, code) in &[
+            ("Knight", "NAGT"),
+            ("Mitchell", "MATCAL"),
+            ("Macintosh", "MCANT"),
+            ("Phillips", "FALAP"),
+            ("Howard", "HAD"),
+            ("Bowman", "BANAN"),
+            ("Lowe", "L"),
+            ("Lakha", "LAC"),
+            ("", ""),
+        ] {
+            assert_eq!(nysiis(word), code, "{}", word);
+        }
+    }
+
+    #[test]
+    fn double_metaphone_codes() {
+        for &(word, primary, alternate) in &[
+            ("Smith", "SM0", "XMT"),
+            ("Schmidt", "XMT", "SMT"),
+            ("Thumb", "0M", "TM"),
+            ("Michael", "MKL", "MXL"),
+            ("Katherine", "K0RN", "KTRN"),
+            ("Catherine", "K0RN", "KTRN"),
+            ("Jose", "HS", "HS"),
+            ("Wasserman", "ASRM", "FSRM"),
+            ("Gnome", "NM", "NM"),
+        ] {
+            assert_eq!(
+                double_metaphone(word),
+                (primary.to_string(), alternate.to_string()),
+                "{}",
+                word
+            );
+        }
+    }
+
+    #[test]
+    fn codes_per_word() {
+        assert_eq!(Encoding::Soundex.codes("John Smith"), vec!["J500 S530"]);
+        assert_eq!(
+            Encoding::Metaphone.codes("john smith"),
+            vec!["JN SM0", "JN XMT", "AN SM0", "AN XMT"]
+        );
+    }
+
+    #[test]
+    fn no_letters_no_codes() {
+        for encoding in [Encoding::Soundex, Encoding::Metaphone, Encoding::Nysiis]
+            .iter()
+            .cloned()
+        {
+            for &record in &["12345", "---", ""] {
+                assert!(encoding.codes(record).is_empty(), "{:?}", record);
+            }
+        }
+    }
+}
@@ -1,19 +1,26 @@
-use crate::cluster::{Cluster, ClusterAlgo};
-use crate::cluster_output::{ClusterOutput, FieldSeperator as OFS, RecordSeperator as ORS};
-use crate::doc_reader::{DocReader, RecordSeperator as IRS};
 use clap::ArgMatches;
 use std::fs::File;
-use std::io;
+use std::io::{self, Write};
 use std::process::exit;
+use txt_cluster::cluster::{
+    BkTree, Dendrogram, KMedoids, Leader, Linkage, Metric, MinHash, Order, Representative,
+    Singletons,
+};
+use txt_cluster::cluster_output::{FieldSeperator as OFS, Format, RecordSeperator as ORS, Tree};
+use txt_cluster::doc_reader::RecordSeperator as IRS;
+use txt_cluster::mask::{MaskClass, Masker};
+use txt_cluster::normalize::{Form, Normalizer};
+use txt_cluster::{Cluster, ClusterAlgo, ClusterOutput, DocReader};
 
 pub fn docs(matches: &ArgMatches) -> Vec<String> {
+    records(matches).collect()
+}
+
+fn records(matches: &ArgMatches) -> Box<dyn Iterator<Item = String>> {
     match matches.value_of("file") {
-        None => {
-            let sin = io::stdin();
-            DocReader::new(sin.lock(), irs(&matches)).collect()
-        }
+        None => Box::new(DocReader::with_read(io::stdin(), irs(matches))),
         Some(path) => match std::fs::File::open(path) {
-            Ok(file) => DocReader::with_read(file, irs(&matches)).collect(),
+            Ok(file) => Box::new(DocReader::with_read(file, irs(matches))),
             Err(err) => {
                 eprintln!("Error opening '{}': {}", path, err);
                 std::process::exit(1);
@@ -49,32 +56,329 @@ fn ors(matches: &ArgMatches) -> ORS {
     }
 }
 
+fn format(matches: &ArgMatches) -> Format {
+    match matches.value_of("format") {
+        Some("text") | None => Format::Text,
+        Some("json") => Format::Json,
+        Some("ndjson") => Format::Ndjson,
+        Some("label") => Format::Label {
+            size: matches.is_present("size"),
+        },
+        _ => unreachable!(),
+    }
+}
+
+fn order(matches: &ArgMatches) -> Order {
+    match matches.value_of("sort") {
+        Some("first") | None => Order::First,
+        Some("size") => Order::Size,
+        Some("lex") => Order::Lex,
+        _ => unreachable!(),
+    }
+}
+
+fn linkage(matches: &ArgMatches) -> Linkage {
+    match matches.value_of("linkage") {
+        Some("single") | None => Linkage::Single,
+        Some("complete") => Linkage::Complete,
+        Some("average") => Linkage::Average,
+        _ => unreachable!(),
+    }
+}
+
+fn tree(matches: &ArgMatches) -> Option<Tree> {
+    match matches.value_of("dendrogram") {
+        Some("newick") => Some(Tree::Newick),
+        Some("json") => Some(Tree::Json),
+        None => None,
+        _ => unreachable!(),
+    }
+}
+
+fn cuts(matches: &ArgMatches) -> Vec<f64> {
+    matches
+        .values_of("cut")
+        .into_iter()
+        .flatten()
+        .map(|cut| cut.parse::<f64>().unwrap())
+        .collect()
+}
+
+fn representative(matches: &ArgMatches) -> Option<Representative> {
+    match matches.value_of("representative") {
+        Some("medoid") => Some(Representative::Medoid),
+        Some("frequent") => Some(Representative::Frequent),
+        Some("shortest") => Some(Representative::Shortest),
+        Some("first") => Some(Representative::First),
+        None if matches.is_present("representatives-only") => Some(Representative::Medoid),
+        None => None,
+        _ => unreachable!(),
+    }
+}
+
+fn masker(matches: &ArgMatches) -> Option<Masker> {
+    if !matches.is_present("mask") && !matches.is_present("mask-pattern") {
+        return None;
+    }
+
+    let mut masker = Masker::new();
+    for class in matches.values_of("mask").into_iter().flatten() {
+        masker = match class {
+            "all" => Masker::all(),
+            "uuid" => masker.class(MaskClass::Uuid),
+            "date" => masker.class(MaskClass::Date),
+            "ip" => masker.class(MaskClass::Ip),
+            "hex" => masker.class(MaskClass::Hex),
+            "num" => masker.class(MaskClass::Number),
+            _ => unreachable!(),
+        }
+    }
+
+    for pattern in matches.values_of("mask-pattern").into_iter().flatten() {
+        masker = masker.pattern(pattern, "<VAR>").unwrap();
+    }
+
+    Some(masker)
+}
+
+/// The configured normalizer, leaving the placeholders of `masker` intact.
+fn normalizer(matches: &ArgMatches, masker: Option<&Masker>) -> Option<Normalizer> {
+    let steps = matches.values_of("normalize")?;
+    let normalizer = steps.fold(Normalizer::new(), |normalizer, step| match step {
+        "all" => Normalizer::all(),
+        "nfc" => normalizer.form(Form::Nfc),
+        "nfkc" => normalizer.form(Form::Nfkc),
+        "diacritics" => normalizer.diacritics(true),
+        "lower" => normalizer.lowercase(true),
+        "punctuation" => normalizer.punctuation(true),
+        "whitespace" => normalizer.whitespace(true),
+        "trim" => normalizer.trim(true),
+        _ => unreachable!(),
+    });
+
+    Some(match masker {
+        Some(masker) => normalizer.keep(&masker.placeholders()),
+        None => normalizer,
+    })
+}
+
+fn singletons(matches: &ArgMatches) -> Singletons {
+    match matches.value_of("singletons") {
+        None if matches.value_of("format") == Some("label") => Singletons::Include,
+        None if matches.is_present("medoids") => Singletons::Include,
+        Some("exclude") | None => Singletons::Exclude,
+        Some("include") => Singletons::Include,
+        Some("only") => Singletons::Only,
+        _ => unreachable!(),
+    }
+}
+
+fn count(matches: &ArgMatches, name: &str, default: usize) -> usize {
+    matches
+        .value_of(name)
+        .map_or(default, |value| value.parse::<usize>().unwrap())
+}
+
 pub fn run_cluster<CA>(matches: &ArgMatches, algo: CA)
 where
     CA: ClusterAlgo,
 {
+    run_fitted(matches, |_| algo);
+}
+
+/// Clusters with the algorithm `fit` builds from the keys that will be compared.
+pub fn run_fitted<CA, F>(matches: &ArgMatches, fit: F)
+where
+    CA: ClusterAlgo,
+    F: FnOnce(&[String]) -> CA,
+{
+    if matches.is_present("stream") {
+        stream(matches, fit(&[]));
+        return;
+    }
+
     let lines = docs(matches);
-    let clusters = algo.cluster(&lines);
-    print_cluster(matches, clusters);
+    let masker = masker(matches);
+    let masked = masker.as_ref().map(|masker| masker.masks(&lines));
+    let masked = masked.as_ref().unwrap_or(&lines);
+
+    let normalized =
+        normalizer(matches, masker.as_ref()).map(|normalizer| normalizer.normalize_all(masked));
+    let keys = normalized.as_ref().unwrap_or(masked);
+
+    let algo = fit(keys);
+    if !matches.is_present("bands") {
+        cluster(matches, &lines, keys, &algo);
+        return;
+    }
+
+    let algo = MinHash::new(algo)
+        .bands(count(matches, "bands", 16))
+        .rows(count(matches, "rows", 4))
+        .shingle(count(matches, "shingle", 3));
+
+    cluster(matches, &lines, keys, &algo);
+
+    match algo.estimated_recall() {
+        Some(recall) => eprintln!("minhash: estimated recall {:.3}", recall),
+        None => eprintln!("minhash: no pairs found"),
+    }
 }
 
-fn print_cluster(matches: &ArgMatches, cluster: Cluster<'_>) {
-    match matches.value_of("output") {
-        None => {
-            let mut out = io::stdout();
-            ClusterOutput::new(&mut out, ofs(matches), ors(matches)).output(cluster);
-        }
+/// Assigns records to leaders as they are read, writing each assignment
+/// before reading the next record.
+fn stream<CA>(matches: &ArgMatches, algo: CA)
+where
+    CA: ClusterAlgo,
+{
+    let masker = masker(matches);
+    let normalizer = normalizer(matches, masker.as_ref());
+    let mut leader = Leader::new(algo);
 
-        Some(path) => {
-            let mut file = match File::create(path) {
-                Ok(f) => f,
-                Err(err) => {
-                    eprintln!("Failed to open '{}': {}", path, err);
-                    exit(1)
-                }
-            };
+    let mut write = writer(matches);
+    let mut output = output(matches, &mut write);
 
-            ClusterOutput::new(&mut file, ofs(matches), ors(matches)).output(cluster);
+    for (index, record) in records(matches).enumerate() {
+        let key = match &masker {
+            Some(masker) => masker.mask(&record),
+            None => record.clone(),
+        };
+        let key = match &normalizer {
+            Some(normalizer) => normalizer.normalize(&key),
+            None => key,
+        };
+
+        let id = leader.assign(&key);
+        output.output_assignment(index, id, leader.size(id), &record);
+    }
+}
+
+fn cluster<CA>(matches: &ArgMatches, lines: &[String], keys: &[String], algo: &CA)
+where
+    CA: ClusterAlgo,
+{
+    if matches.is_present("medoids") {
+        let mut medoids = KMedoids::new(count(matches, "medoids", 1));
+        if matches.is_present("samples") {
+            medoids = medoids.samples(count(matches, "samples", 5));
+        }
+        if matches.is_present("sample-size") {
+            medoids = medoids.sample_size(count(matches, "sample-size", 500));
         }
+
+        let clusters = medoids.cluster(algo, lines, keys);
+        print_cluster(matches, finish(matches, keys, algo, clusters));
+        return;
+    }
+
+    if matches.is_present("min-points") {
+        let clusters = algo.cluster_dense(lines, keys, count(matches, "min-points", 1));
+        print_cluster(matches, finish(matches, keys, algo, clusters));
+        return;
+    }
+
+    if !matches.is_present("cut") && !matches.is_present("dendrogram") {
+        let clusters = algo.cluster_linked(lines, keys, linkage(matches));
+        print_cluster(matches, finish(matches, keys, algo, clusters));
+        return;
+    }
+
+    let dendrogram = Dendrogram::new(algo, lines, keys, linkage(matches));
+    let mut write = writer(matches);
+    let mut output = output(matches, &mut write);
+
+    if let Some(tree) = tree(matches) {
+        output.output_dendrogram(&dendrogram, tree);
+        return;
+    }
+
+    let mut cuts = cuts(matches)
+        .into_iter()
+        .map(|cut| {
+            let clusters = dendrogram.cut(cut);
+            (cut, finish(matches, keys, algo, clusters))
+        })
+        .collect::<Vec<(f64, Cluster<'_>)>>();
+
+    if cuts.len() == 1 {
+        output.output(cuts.pop().unwrap().1);
+    } else {
+        output.output_cuts(cuts);
+    }
+}
+
+/// Applies the singleton, ordering, representative and template options,
+/// deriving templates from the compared `keys`.
+fn finish<'a, CA>(
+    matches: &ArgMatches,
+    keys: &[String],
+    algo: &CA,
+    mut clusters: Cluster<'a>,
+) -> Cluster<'a>
+where
+    CA: ClusterAlgo,
+{
+    clusters = clusters
+        .singletons(singletons(matches))
+        .sort(order(matches));
+
+    let pick = match representative(matches) {
+        // k-medoids already chose every medoid
+        Some(_) if matches.is_present("medoids") && !matches.is_present("representative") => None,
+        pick => pick,
+    };
+
+    if let Some(pick) = pick {
+        clusters = clusters.representatives(pick, algo);
+    }
+
+    if matches.is_present("medoids") {
+        clusters = clusters.representatives_first();
+    }
+
+    if matches.is_present("templates") {
+        clusters = clusters.templates(keys);
+    }
+
+    clusters
+}
+
+pub fn run_metric<M>(matches: &ArgMatches, algo: M)
+where
+    M: Metric,
+{
+    match matches.value_of("index") {
+        Some("bk-tree") => run_cluster(matches, BkTree::new(algo)),
+        Some("brute") | None => run_cluster(matches, algo),
+        _ => unreachable!(),
     }
 }
+
+fn writer(matches: &ArgMatches) -> Box<dyn Write> {
+    match matches.value_of("output") {
+        None => Box::new(io::stdout()),
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(file),
+            Err(err) => {
+                eprintln!("Failed to open '{}': {}", path, err);
+                exit(1)
+            }
+        },
+    }
+}
+
+fn output<'w, W>(matches: &ArgMatches, write: &'w mut W) -> ClusterOutput<'w, W>
+where
+    W: Write,
+{
+    ClusterOutput::new(write, ofs(matches), ors(matches))
+        .format(format(matches))
+        .representatives(matches.is_present("representatives-only"))
+        .templates(matches.is_present("templates"))
+        .counts(matches.is_present("counts"))
+}
+
+fn print_cluster(matches: &ArgMatches, cluster: Cluster<'_>) {
+    let mut write = writer(matches);
+    output(matches, &mut write).output(cluster);
+}
@@ -1,11 +1,27 @@
 pub fn nonnegative(value: String) -> Result<(), String> {
-    match i32::from_str_radix(&value, 10) {
+    match value.parse::<i32>() {
         Ok(i) if i < 0 => Err(format!("{} is negative", i)),
         Err(_) => Err(format!("{} not a number", value)),
         _ => Ok(()),
     }
 }
 
+pub fn positive(value: String) -> Result<(), String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err(format!("{} is not positive", value)),
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("{} not a positive number", value)),
+    }
+}
+
+pub fn nonnegative_float(value: String) -> Result<(), String> {
+    match value.parse::<f64>() {
+        Ok(f) if f >= 0.0 => Ok(()),
+        Ok(_) => Err(format!("{} is negative", value)),
+        Err(_) => Err(format!("{} is not a float", value)),
+    }
+}
+
 pub fn ratio(value: String) -> Result<(), String> {
     match value.parse::<f64>() {
         Ok(f) if 0.0 < f && f < 1.0 => Ok(()),
@@ -13,3 +29,10 @@ pub fn ratio(value: String) -> Result<(), String> {
         Err(_) => Err(format!("{} is not a float", value)),
     }
 }
+
+pub fn regex(value: String) -> Result<(), String> {
+    match regex::Regex::new(&value) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{} is not a valid regex: {}", value, err)),
+    }
+}